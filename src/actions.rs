@@ -0,0 +1,174 @@
+use crate::app::AppEvent;
+use crate::ec2::{Ec2Client, WaitFor};
+use crate::errors::FriendlyError;
+use crate::operations::Cancel;
+use tokio::sync::mpsc;
+
+/// Runs the stop -> modify -> credit spec -> start pipeline, reporting each step on `tx`.
+/// Failures are sent as `AppEvent::Error` or `AppEvent::Failed`; returns whether the resize
+/// went through.
+///
+/// With `restart` unset an instance that was already stopped is left stopped; otherwise the
+/// resize only counts once the instance is running again. `cancel` is checked before every
+/// mutating step, and also ends the waits.
+pub async fn resize_instance(
+    client: &Ec2Client,
+    instance_id: &str,
+    new_type: &str,
+    credit_spec: &str,
+    restart: bool,
+    cancel: &Cancel,
+    tx: &mpsc::Sender<AppEvent>,
+) -> bool {
+    let step = |step: &str| AppEvent::Progress(instance_id.to_string(), step.to_string());
+    let cancelled =
+        |outcome: &str| AppEvent::Error(format!("Resize cancelled; {} {}.", instance_id, outcome));
+
+    // Stopping would be rejected anyway; fail before touching anything
+    match client.get_protection(instance_id).await {
+        Ok(protection) if protection.stop => {
+            let _ = tx
+                .send(AppEvent::Error(format!(
+                    "Stop protection is enabled for {}. Disable it before resizing.",
+                    instance_id
+                )))
+                .await;
+            return false;
+        }
+        Ok(_) => {}
+        Err(e) => {
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new(
+                    "Protection check failed",
+                    &e,
+                )))
+                .await;
+            return false;
+        }
+    }
+
+    // 1. Stop
+    if cancel.is_cancelled() {
+        let _ = tx.send(cancelled("was not changed")).await;
+        return false;
+    }
+    if let Err(e) = client.stop_instance(instance_id).await {
+        let _ = tx
+            .send(AppEvent::Failed(FriendlyError::new("Stop failed", &e)))
+            .await;
+        return false;
+    }
+
+    let _ = tx.send(step("Waiting for stop...")).await;
+    // A cancelled wait falls through to the check below
+    if let Err(e) = client.wait_for(instance_id, WaitFor::Stopped, cancel).await
+        && !cancel.is_cancelled()
+    {
+        let _ = tx
+            .send(AppEvent::Failed(FriendlyError::new("Wait failed", &e)))
+            .await;
+        return false;
+    }
+
+    // 2. Modify
+    if cancel.is_cancelled() {
+        let _ = tx
+            .send(cancelled("is stopping (or stopped) with its old type"))
+            .await;
+        return false;
+    }
+    let _ = tx.send(step(&format!("Changing to {}...", new_type))).await;
+    if let Err(e) = client.modify_instance_type(instance_id, new_type).await {
+        let _ = tx
+            .send(AppEvent::Failed(FriendlyError::new("Modify failed", &e)))
+            .await;
+        return false;
+    }
+
+    // 3. T-credit
+    if new_type.starts_with("t") {
+        let _ = tx.send(step("Setting credit spec...")).await;
+        if let Err(e) = client
+            .modify_credit_specification(instance_id, credit_spec)
+            .await
+        {
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new(
+                    "Credit spec failed",
+                    &e,
+                )))
+                .await;
+        }
+    }
+
+    // 4. Start
+    if restart {
+        if cancel.is_cancelled() {
+            let _ = tx
+                .send(cancelled(&format!("is stopped as {}", new_type)))
+                .await;
+            return false;
+        }
+        let _ = tx.send(step("Starting...")).await;
+        if let Err(e) = client.start_instance(instance_id).await {
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new("Start failed", &e)))
+                .await;
+            return false;
+        }
+
+        let _ = tx.send(step("Waiting for running...")).await;
+        if let Err(e) = client.wait_for(instance_id, WaitFor::Running, cancel).await {
+            let _ = if cancel.is_cancelled() {
+                tx.send(cancelled(&format!("is starting as {}", new_type)))
+                    .await
+            } else {
+                tx.send(AppEvent::Failed(FriendlyError::new(
+                    "Start did not complete",
+                    &e,
+                )))
+                .await
+            };
+            return false;
+        }
+    }
+
+    true
+}
+
+/// After EC2 accepted a request for `instance_id`, waits for each of `targets` in turn and
+/// then reports `done` (or why a wait ended) followed by a refresh. The refresh goes first on
+/// failure so the error stays on screen.
+pub async fn await_completion(
+    client: &Ec2Client,
+    instance_id: &str,
+    targets: &[WaitFor],
+    done: &str,
+    cancel: &Cancel,
+    tx: &mpsc::Sender<AppEvent>,
+) {
+    for &target in targets {
+        let _ = tx
+            .send(AppEvent::Progress(
+                instance_id.to_string(),
+                format!("Waiting for {}...", target.label()),
+            ))
+            .await;
+        if let Err(e) = client.wait_for(instance_id, target, cancel).await {
+            let context = if target == WaitFor::StatusOk {
+                "Status checks failed"
+            } else {
+                "Wait failed"
+            };
+            let _ = tx.send(AppEvent::InstancesUpdated).await;
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new(context, &e)))
+                .await;
+            return;
+        }
+    }
+    let _ = tx
+        .send(AppEvent::Message(format!("{} Refreshing...", done)))
+        .await;
+    let _ = tx.send(AppEvent::InstancesUpdated).await;
+}
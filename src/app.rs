@@ -1,5 +1,16 @@
-use crate::config::AppConfig;
-use crate::ec2::{Ec2Client, InstanceInfo, InstanceTypeInfo};
+use crate::audit::AuditRecord;
+use crate::cloudwatch::{CloudWatchClient, InstanceMetrics, MetricsWindow};
+use crate::config::{AppConfig, Endpoints, PolicyAction};
+use crate::ec2::{
+    Ec2Client, InstanceInfo, InstanceTypeInfo, PermissionCheck, Protection, SpotRequestInfo,
+    StatusChecks, Waiters,
+};
+use crate::errors::{ErrorKind, FriendlyError};
+use crate::fake::FakePricing;
+use crate::idle::IdleCandidate;
+use crate::operations::Operations;
+use crate::recording::Traffic;
+use crate::rightsizing::{Recommendation, UtilizationStats};
 use anyhow::Result;
 use ratatui::widgets::TableState;
 use tokio::sync::mpsc;
@@ -15,17 +26,151 @@ pub enum AppState {
         default_mode_active: bool,
     }, // Input buffer and list of visible options
     ConfirmReboot(String), // Instance ID
-    Processing(String),    // Message to show
+    SpotDetails {
+        instance_id: String,
+        request: Option<SpotRequestInfo>,
+    }, // None while the request is being fetched
+    Recommendations {
+        instance_id: String,
+        stats: Option<UtilizationStats>,
+        recommendations: Vec<Recommendation>,
+        selected_index: Option<usize>,
+    },
+    IdleInstances {
+        candidates: Vec<IdleCandidate>,
+        selected_index: Option<usize>,
+        scanning: bool,
+    },
+    AuditLog {
+        records: Option<Vec<AuditRecord>>, // None while loading
+        instance_filter: String,
+        action_filter: Option<usize>, // Index into audit::ACTIONS
+        selected_index: Option<usize>,
+    },
+    PermissionReport {
+        instance_id: String,
+        checks: Option<Vec<PermissionCheck>>,
+    }, // None while the dry runs are in flight
+    ConfirmAction {
+        action: PendingAction,
+        expected: Option<String>, // Text that must be typed back, if the policy asks for it
+        input: String,
+    },
+    Processing(String), // Message to show
+}
+
+/// A mutating action waiting on the policy check (and possibly a confirmation prompt).
+#[derive(Clone, Debug, PartialEq)]
+pub enum PendingAction {
+    Start(String),
+    Stop(String),
+    Hibernate(String),
+    Reboot {
+        instance_id: String,
+        force: bool,
+    },
+    Resize {
+        instance_id: String,
+        new_type: String,
+    },
+    ToggleProtection {
+        instance_id: String,
+        stop: bool,
+    },
+    BulkStop(Vec<String>),
+}
+
+impl PendingAction {
+    pub fn kind(&self) -> PolicyAction {
+        match self {
+            PendingAction::Start(_) => PolicyAction::Start,
+            PendingAction::Stop(_) | PendingAction::BulkStop(_) => PolicyAction::Stop,
+            PendingAction::Hibernate(_) => PolicyAction::Hibernate,
+            PendingAction::Reboot { .. } => PolicyAction::Reboot,
+            PendingAction::Resize { .. } => PolicyAction::Resize,
+            PendingAction::ToggleProtection { .. } => PolicyAction::Protection,
+        }
+    }
+
+    pub fn instance_ids(&self) -> Vec<&str> {
+        match self {
+            PendingAction::Start(id) | PendingAction::Stop(id) | PendingAction::Hibernate(id) => {
+                vec![id.as_str()]
+            }
+            PendingAction::Reboot { instance_id, .. }
+            | PendingAction::Resize { instance_id, .. }
+            | PendingAction::ToggleProtection { instance_id, .. } => vec![instance_id.as_str()],
+            PendingAction::BulkStop(ids) => ids.iter().map(|id| id.as_str()).collect(),
+        }
+    }
+
+    /// Short description for the confirmation prompt, e.g. "Stop i-0abc".
+    pub fn describe(&self) -> String {
+        match self {
+            PendingAction::Start(id) => format!("Start {}", id),
+            PendingAction::Stop(id) => format!("Stop {}", id),
+            PendingAction::Hibernate(id) => format!("Hibernate {}", id),
+            PendingAction::Reboot { instance_id, force } => {
+                if *force {
+                    format!("Force reboot {}", instance_id)
+                } else {
+                    format!("Reboot {}", instance_id)
+                }
+            }
+            PendingAction::Resize {
+                instance_id,
+                new_type,
+            } => format!("Resize {} to {}", instance_id, new_type),
+            PendingAction::ToggleProtection { instance_id, stop } => format!(
+                "Toggle {} protection on {}",
+                if *stop { "stop" } else { "termination" },
+                instance_id
+            ),
+            PendingAction::BulkStop(ids) => format!("Stop {} instances", ids.len()),
+        }
+    }
 }
 
 pub enum AppEvent {
     Error(String),
+    Failed(FriendlyError), // An AWS call failed; the raw error can be expanded
     Message(String),
+    Progress(String, String), // Instance ID, step an operation on it has reached
+    OperationFinished(u64),   // Sent last by every operation, however it ended
     InstancesUpdated,
     InstancesFetched(Vec<InstanceInfo>),
+    StatusChecksFetched(std::collections::HashMap<String, StatusChecks>),
+    InstanceTypesFetched(Vec<InstanceTypeInfo>),
+    Loaded(Load), // A startup fetch finished, whether or not it succeeded
 
     BulkOnDemandFetched(std::collections::HashMap<String, f64>),
     BulkSpotFetched(std::collections::HashMap<String, f64>),
+    SpotAdvisorFetched(crate::spot_advisor::SpotAdvisor),
+    SpotRequestFetched(String, SpotRequestInfo), // Instance ID, request
+    MetricsFetched(InstanceMetrics),
+    UtilizationFetched(String, UtilizationStats), // Instance ID, stats
+    IdleScanned(Vec<IdleCandidate>),
+    ProtectionFetched(String, Protection), // Instance ID, protection flags
+    PermissionsChecked(String, Vec<PermissionCheck>), // Instance ID, dry-run results
+    AuditLoaded(Vec<AuditRecord>),
+}
+
+/// Startup data fetched in the background after the first frame.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Load {
+    Instances,
+    InstanceTypes,
+    Prices,
+}
+
+impl Load {
+    pub fn label(self) -> &'static str {
+        match self {
+            Load::Instances => "instances",
+            Load::InstanceTypes => "types",
+            Load::Prices => "prices",
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -50,24 +195,46 @@ pub struct App {
     pub type_prices: std::collections::HashMap<String, Prices>,
     pub instance_type_map: std::collections::HashMap<String, InstanceTypeInfo>,
     pub last_refreshed: Option<String>,
+    pub region: String,
+    pub spot_advisor: Option<crate::spot_advisor::SpotAdvisor>,
+    pub spot_requests: std::collections::HashMap<String, SpotRequestInfo>, // By instance ID
+    // By instance ID; None while the lookup is in flight (or failed)
+    pub protection: std::collections::HashMap<String, Option<Protection>>,
+    // By instance ID, running instances only; refreshed with the list
+    pub status_checks: std::collections::HashMap<String, StatusChecks>,
+    pub cloudwatch_client: Option<CloudWatchClient>,
+    pub show_metrics: bool,
+    pub metrics_window: MetricsWindow,
+    pub metrics: Option<InstanceMetrics>,
+    pub metrics_requested_for: Option<String>,
+    pub schedule_rules: Vec<crate::schedule::CompiledRule>,
+    pub resize_rules: Vec<crate::schedule::CompiledResizeRule>,
+    pub read_only: bool,
+    pub endpoints: Endpoints, // Kept so reloaded clients use the same endpoints
+    pub traffic: Option<Traffic>, // Set while recording or replaying a session
+    pub error_detail: Option<String>, // Raw error behind the current error popup
+    pub error_expanded: bool,
+    // Set when AWS rejected our credentials; auto-refresh pauses until they are reloaded
+    pub credentials_problem: Option<ErrorKind>,
+    pub loading: Vec<Load>, // Startup fetches still in flight
+    pub operations: Operations,
+    pub spinner: usize, // Animation frame for the loading spinners
 }
 
 impl App {
-    pub async fn new() -> Result<Self> {
+    /// `read_only` comes from the command line and adds to the config setting, while
+    /// `endpoint_overrides` replace the configured endpoints.
+    pub async fn new(
+        read_only: bool,
+        endpoint_overrides: &Endpoints,
+        traffic: Option<Traffic>,
+    ) -> Result<Self> {
         let config = AppConfig::load().await?;
-        let ec2_client = Ec2Client::new().await?;
-        // Fetch instances and types concurrently or sequentially
-        let instances = ec2_client.list_instances().await?;
-        let instance_types = ec2_client
-            .get_instance_types()
-            .await
-            .unwrap_or_else(|_| vec![]);
-        let mut instance_type_map = std::collections::HashMap::new();
-        for t in &instance_types {
-            instance_type_map.insert(t.name.clone(), t.clone());
-        }
-        let mut list_state = TableState::default();
-        list_state.select(Some(0));
+        let read_only = read_only || config.read_only.unwrap_or(false);
+        let endpoints = config.endpoints(endpoint_overrides);
+        let ec2_client = Ec2Client::new(endpoints.ec2.as_deref(), traffic.as_ref())
+            .await?
+            .with_read_only(read_only);
 
         // Init PricingClient
         let region_provider =
@@ -79,17 +246,75 @@ impl App {
         let region = aws_config
             .region()
             .map(|r| r.as_ref())
-            .unwrap_or("us-east-1");
+            .unwrap_or("us-east-1")
+            .to_string();
+
+        let pricing_client = crate::pricing::PricingClient::new(
+            &region,
+            endpoints.pricing.as_deref(),
+            traffic.as_ref(),
+        )
+        .await
+        .ok();
+        let cloudwatch_client = CloudWatchClient::new().await.ok();
+
+        let mut app = Self::with_clients(
+            config,
+            read_only,
+            ec2_client,
+            pricing_client,
+            cloudwatch_client,
+            region,
+        )?;
+        app.endpoints = endpoints;
+        app.traffic = traffic;
+        Ok(app)
+    }
 
-        let pricing_client = crate::pricing::PricingClient::new(region).await.ok();
+    /// An app around a generated fleet with built-in prices, for screenshots and training.
+    /// It needs no credentials and leaves the config file alone.
+    pub async fn new_demo(read_only: bool) -> Result<Self> {
+        let ec2_client = Ec2Client::from_backend(std::sync::Arc::new(crate::demo::fleet()))
+            .with_read_only(read_only);
+        let pricing_client =
+            crate::pricing::PricingClient::from_backend(std::sync::Arc::new(FakePricing {
+                prices: crate::demo::on_demand_prices(),
+            }));
+        Self::with_clients(
+            AppConfig::default(),
+            read_only,
+            ec2_client,
+            Some(pricing_client),
+            None,
+            crate::demo::REGION.to_string(),
+        )
+    }
+
+    /// Builds the app without waiting on AWS; instances, types and prices load in the background.
+    fn with_clients(
+        config: AppConfig,
+        read_only: bool,
+        ec2_client: Ec2Client,
+        pricing_client: Option<crate::pricing::PricingClient>,
+        cloudwatch_client: Option<CloudWatchClient>,
+        region: String,
+    ) -> Result<Self> {
+        let schedule_rules =
+            crate::schedule::CompiledRule::compile_all(config.schedules.as_deref().unwrap_or(&[]))?;
+        let resize_rules = crate::schedule::CompiledResizeRule::compile_all(
+            config.resize_schedules.as_deref().unwrap_or(&[]),
+        )?;
+        let ec2_client = ec2_client.with_waiters(Waiters::from_config(&config));
+        let mut list_state = TableState::default();
+        list_state.select(Some(0));
 
         let (event_tx, event_rx) = mpsc::channel(10);
 
         let mut app = Self {
             should_quit: false,
-            instances: instances.clone(),
-            filtered_instances: instances,
-            instance_types,
+            instances: vec![],
+            filtered_instances: vec![],
+            instance_types: vec![],
             list_state,
             ec2_client,
             pricing_client,
@@ -99,13 +324,171 @@ impl App {
             event_tx,
             event_rx,
             type_prices: std::collections::HashMap::new(),
-            instance_type_map,
-            last_refreshed: Some(chrono::Local::now().format("%H:%M:%S").to_string()),
+            instance_type_map: std::collections::HashMap::new(),
+            last_refreshed: None,
+            region,
+            spot_advisor: None,
+            spot_requests: std::collections::HashMap::new(),
+            protection: std::collections::HashMap::new(),
+            status_checks: std::collections::HashMap::new(),
+            cloudwatch_client,
+            show_metrics: false,
+            metrics_window: MetricsWindow::OneHour,
+            metrics: None,
+            metrics_requested_for: None,
+            schedule_rules,
+            resize_rules,
+            read_only,
+            endpoints: Endpoints::default(),
+            traffic: None,
+            error_detail: None,
+            error_expanded: false,
+            credentials_problem: None,
+            loading: vec![],
+            spinner: 0,
+            operations: Operations::default(),
         };
-        app.update_filter();
+        app.start_loading();
         Ok(app)
     }
 
+    /// Spawns the startup fetches. Each reports its data (or a failure) and then `Loaded`.
+    fn start_loading(&mut self) {
+        self.loading = vec![Load::Instances, Load::InstanceTypes];
+
+        let client = self.ec2_client.clone();
+        let tx = self.event_tx.clone();
+        tokio::spawn(async move {
+            match client.list_instances().await {
+                Ok(instances) => {
+                    let _ = tx.send(AppEvent::InstancesFetched(instances)).await;
+                }
+                Err(e) => {
+                    let _ = tx
+                        .send(AppEvent::Failed(FriendlyError::new(
+                            "Could not list instances",
+                            &e,
+                        )))
+                        .await;
+                }
+            }
+            let _ = tx.send(AppEvent::Loaded(Load::Instances)).await;
+        });
+
+        let client = self.ec2_client.clone();
+        let tx = self.event_tx.clone();
+        tokio::spawn(async move {
+            // The selector falls back to a short built-in list if this fails
+            if let Ok(types) = client.get_instance_types().await {
+                let _ = tx.send(AppEvent::InstanceTypesFetched(types)).await;
+            }
+            let _ = tx.send(AppEvent::Loaded(Load::InstanceTypes)).await;
+        });
+
+        if let Some(pc) = self.pricing_client.clone() {
+            self.loading.push(Load::Prices);
+            let tx = self.event_tx.clone();
+            tokio::spawn(async move {
+                if let Ok(prices) = pc.fetch_all_on_demand_prices().await {
+                    let _ = tx.send(AppEvent::BulkOnDemandFetched(prices)).await;
+                }
+                let _ = tx.send(AppEvent::Loaded(Load::Prices)).await;
+            });
+        }
+    }
+
+    /// An app around `ec2_client` with default config and no other AWS clients.
+    #[cfg(test)]
+    pub fn for_test(ec2_client: Ec2Client, instances: Vec<InstanceInfo>) -> Self {
+        let (tx, rx) = mpsc::channel(100);
+
+        Self {
+            should_quit: false,
+            instances: instances.clone(),
+            filtered_instances: instances,
+            instance_types: vec![],
+            list_state: TableState::default(),
+            ec2_client,
+            pricing_client: None,
+            config: AppConfig {
+                default_instance_type: None,
+                refresh_interval_seconds: Some(5),
+                filter: None,
+                t_family_credit: None,
+                spot_advisor_source: None,
+                rightsizing_lookback_days: None,
+                idle_cpu_threshold_percent: None,
+                idle_network_threshold_bytes: None,
+                idle_hours: None,
+                schedules: None,
+                resize_schedules: None,
+                policies: None,
+                read_only: None,
+                login_command: None,
+                ec2_endpoint_url: None,
+                pricing_endpoint_url: None,
+                wait_interval_seconds: None,
+                wait_max_interval_seconds: None,
+                wait_timeout_seconds: None,
+                status_check_timeout_seconds: None,
+                wait_for_status_checks: None,
+            },
+            filter: String::new(),
+            state: AppState::List,
+            event_tx: tx,
+            event_rx: rx,
+            type_prices: std::collections::HashMap::new(),
+            instance_type_map: std::collections::HashMap::new(),
+            last_refreshed: None,
+            region: "us-east-1".to_string(),
+            spot_advisor: None,
+            spot_requests: std::collections::HashMap::new(),
+            protection: std::collections::HashMap::new(),
+            status_checks: std::collections::HashMap::new(),
+            cloudwatch_client: None,
+            show_metrics: false,
+            metrics_window: MetricsWindow::OneHour,
+            metrics: None,
+            metrics_requested_for: None,
+            schedule_rules: vec![],
+            resize_rules: vec![],
+            read_only: false,
+            endpoints: Endpoints::default(),
+            traffic: None,
+            error_detail: None,
+            error_expanded: false,
+            credentials_problem: None,
+            loading: vec![],
+            spinner: 0,
+            operations: Operations::default(),
+        }
+    }
+
+    /// Rebuilds the AWS clients so they pick up renewed credentials, e.g. after
+    /// `aws sso login`. The clients are only replaced once a fresh listing succeeds.
+    pub async fn reload_clients(&mut self) -> Result<()> {
+        let ec2_client = Ec2Client::new(self.endpoints.ec2.as_deref(), self.traffic.as_ref())
+            .await?
+            .with_read_only(self.read_only)
+            .with_waiters(Waiters::from_config(&self.config));
+        let instances = ec2_client.list_instances().await?;
+
+        self.ec2_client = ec2_client;
+        self.pricing_client = crate::pricing::PricingClient::new(
+            &self.region,
+            self.endpoints.pricing.as_deref(),
+            self.traffic.as_ref(),
+        )
+        .await
+        .ok();
+        self.cloudwatch_client = CloudWatchClient::new().await.ok();
+        self.instances = instances;
+        self.update_filter();
+        self.last_refreshed = Some(chrono::Local::now().format("%H:%M:%S").to_string());
+        self.credentials_problem = None;
+        Ok(())
+    }
+
     pub fn next(&mut self) {
         let i = match self.list_state.selected() {
             Some(i) => {
@@ -208,6 +591,185 @@ impl App {
         }
     }
 
+    /// Spawns a fetch of the spot request backing `instance`, if it is a spot instance.
+    pub fn fetch_spot_request(&self, instance: &InstanceInfo) {
+        if let Some(request_id) = instance.spot_instance_request_id.clone() {
+            let client = self.ec2_client.clone();
+            let tx = self.event_tx.clone();
+            let instance_id = instance.id.clone();
+            tokio::spawn(async move {
+                match client.describe_spot_request(&request_id).await {
+                    Ok(request) => {
+                        let _ = tx
+                            .send(AppEvent::SpotRequestFetched(instance_id, request))
+                            .await;
+                    }
+                    Err(e) => {
+                        let _ = tx
+                            .send(AppEvent::Failed(FriendlyError::new(
+                                "Spot request lookup failed",
+                                &e,
+                            )))
+                            .await;
+                    }
+                }
+            });
+        }
+    }
+
+    /// Looks up stop/termination protection for instances not seen before. The flags are
+    /// not part of `describe_instances`, so they are fetched once and then kept up to date by
+    /// the toggle actions.
+    pub fn fetch_missing_protection(&mut self) {
+        for instance in &self.instances {
+            if self.protection.contains_key(&instance.id) || instance.state == "terminated" {
+                continue;
+            }
+            self.protection.insert(instance.id.clone(), None);
+            self.fetch_protection(&instance.id);
+        }
+    }
+
+    pub fn fetch_protection(&self, instance_id: &str) {
+        let client = self.ec2_client.clone();
+        let tx = self.event_tx.clone();
+        let instance_id = instance_id.to_string();
+        tokio::spawn(async move {
+            if let Ok(protection) = client.get_protection(&instance_id).await {
+                let _ = tx
+                    .send(AppEvent::ProtectionFetched(instance_id, protection))
+                    .await;
+            }
+        });
+    }
+
+    /// Refreshes the status check columns. Instances missing from the result are not running,
+    /// and a failed lookup just leaves the columns empty.
+    pub fn fetch_status_checks(&self) {
+        let client = self.ec2_client.clone();
+        let tx = self.event_tx.clone();
+        tokio::spawn(async move {
+            if let Ok(checks) = client.list_status_checks().await {
+                let _ = tx.send(AppEvent::StatusChecksFetched(checks)).await;
+            }
+        });
+    }
+
+    /// The strictest policy requirement for `action` over all its targets, with the targets
+    /// that impose it.
+    pub fn policy_requirement(
+        &self,
+        action: &PendingAction,
+    ) -> (crate::policy::Requirement, Vec<String>) {
+        let rules = self.config.policies.as_deref().unwrap_or(&[]);
+        let mut strictest = crate::policy::Requirement::None;
+        let mut offenders = Vec::new();
+        for id in action.instance_ids() {
+            let Some(instance) = self.instances.iter().find(|i| i.id == id) else {
+                continue;
+            };
+            let req = crate::policy::requirement(rules, instance, action.kind());
+            if req > strictest {
+                strictest = req;
+                offenders.clear();
+            }
+            if req == strictest && req != crate::policy::Requirement::None {
+                offenders.push(id.to_string());
+            }
+        }
+        (strictest, offenders)
+    }
+
+    /// Stop protection of `instance_id`, if it is known.
+    pub fn stop_protected(&self, instance_id: &str) -> bool {
+        self.protection
+            .get(instance_id)
+            .copied()
+            .flatten()
+            .is_some_and(|p| p.stop)
+    }
+
+    /// Spawns the bulk On-Demand price fetch, unless prices have already been loaded or are
+    /// still loading from startup.
+    pub fn fetch_on_demand_prices(&self) {
+        if let Some(pc) = self.pricing_client.clone()
+            && self.type_prices.is_empty()
+            && !self.loading.contains(&Load::Prices)
+        {
+            let tx = self.event_tx.clone();
+            tokio::spawn(async move {
+                if let Ok(prices) = pc.fetch_all_on_demand_prices().await {
+                    let _ = tx.send(AppEvent::BulkOnDemandFetched(prices)).await;
+                }
+            });
+        }
+    }
+
+    /// Recomputes the suggestions in `AppState::Recommendations` from the current stats and
+    /// prices. Called whenever either of them arrives.
+    pub fn refresh_recommendations(&mut self) {
+        let AppState::Recommendations {
+            instance_id,
+            stats: Some(stats),
+            recommendations,
+            selected_index,
+        } = &mut self.state
+        else {
+            return;
+        };
+        let Some(instance) = self.instances.iter().find(|i| i.id == *instance_id) else {
+            return;
+        };
+
+        *recommendations = crate::rightsizing::recommend(
+            &instance.instance_type,
+            instance.architecture.as_deref().unwrap_or("x86_64"),
+            stats,
+            &self.instance_type_map,
+            &self.type_prices,
+        );
+        *selected_index = if recommendations.is_empty() {
+            None
+        } else {
+            Some(0)
+        };
+    }
+
+    /// Spawns a CloudWatch fetch for the selected instance over the current metrics window.
+    pub fn fetch_metrics(&mut self) {
+        let Some(client) = self.cloudwatch_client.clone() else {
+            return;
+        };
+        let Some(instance) = self
+            .list_state
+            .selected()
+            .and_then(|i| self.filtered_instances.get(i))
+        else {
+            return;
+        };
+
+        let instance_id = instance.id.clone();
+        let burstable = instance.instance_type.starts_with('t');
+        let window = self.metrics_window;
+        let tx = self.event_tx.clone();
+        self.metrics_requested_for = Some(instance_id.clone());
+        tokio::spawn(async move {
+            match client
+                .fetch_instance_metrics(&instance_id, window, burstable)
+                .await
+            {
+                Ok(metrics) => {
+                    let _ = tx.send(AppEvent::MetricsFetched(metrics)).await;
+                }
+                Err(e) => {
+                    let _ = tx
+                        .send(AppEvent::Error(format!("Metrics fetch failed: {}", e)))
+                        .await;
+                }
+            }
+        });
+    }
+
     pub fn get_selected_architecture(&self) -> Option<String> {
         self.list_state
             .selected()
@@ -220,7 +782,6 @@ impl App {
 mod tests {
     use super::*;
     use crate::ec2::Ec2Client;
-    use tokio::sync::mpsc;
 
     fn create_mock_instance(id: &str, name: Option<&str>, state: &str) -> InstanceInfo {
         InstanceInfo {
@@ -232,35 +793,16 @@ mod tests {
             availability_zone: Some("us-east-1a".to_string()),
             public_ip: None,
             launch_time: None,
+            instance_lifecycle: None,
+            spot_instance_request_id: None,
+            tags: std::collections::HashMap::new(),
+            hibernation_configured: false,
+            state_reason: None,
         }
     }
 
     fn create_test_app(instances: Vec<InstanceInfo>) -> App {
-        let (tx, rx) = mpsc::channel(100);
-        let ec2_client = Ec2Client::new_mock();
-
-        App {
-            should_quit: false,
-            instances: instances.clone(),
-            filtered_instances: instances,
-            instance_types: vec![],
-            list_state: TableState::default(),
-            ec2_client,
-            pricing_client: None,
-            config: AppConfig {
-                default_instance_type: None,
-                refresh_interval_seconds: Some(5),
-                filter: None,
-                t_family_credit: None,
-            },
-            filter: String::new(),
-            state: AppState::List,
-            event_tx: tx,
-            event_rx: rx,
-            type_prices: std::collections::HashMap::new(),
-            instance_type_map: std::collections::HashMap::new(),
-            last_refreshed: None,
-        }
+        App::for_test(Ec2Client::new_mock(), instances)
     }
 
     #[test]
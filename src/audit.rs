@@ -0,0 +1,171 @@
+use crate::config::AppConfig;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::OnceCell;
+
+/// Action names as written to the log, in the order the viewer cycles through them.
+pub const ACTIONS: &[&str] = &[
+    "stop",
+    "start",
+    "reboot",
+    "force-reboot",
+    "hibernate",
+    "resize",
+    "credit-spec",
+    "stop-protection",
+    "termination-protection",
+];
+
+/// One line of the audit log.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    pub timestamp: String, // RFC 3339, UTC
+    pub identity: String,  // Caller ARN
+    pub region: String,
+    pub instance_id: String,
+    pub action: String,
+    pub parameters: BTreeMap<String, String>,
+    pub outcome: String, // "ok" or the error
+}
+
+impl AuditRecord {
+    /// `instance` matches as a case-insensitive substring of the instance ID.
+    pub fn matches(&self, instance: &str, action: Option<&str>) -> bool {
+        self.instance_id
+            .to_lowercase()
+            .contains(&instance.to_lowercase())
+            && action.is_none_or(|a| a == self.action)
+    }
+}
+
+/// Appends a record for each mutating EC2 call.
+pub struct Auditor {
+    sts: aws_sdk_sts::Client,
+    region: String,
+    identity: OnceCell<String>,
+}
+
+impl Auditor {
+    pub fn new(config: &aws_config::SdkConfig) -> Self {
+        Self {
+            sts: aws_sdk_sts::Client::new(config),
+            region: config
+                .region()
+                .map(|r| r.as_ref().to_string())
+                .unwrap_or_default(),
+            identity: OnceCell::new(),
+        }
+    }
+
+    /// Looked up once; a failed lookup is retried with the next record.
+    async fn identity(&self) -> String {
+        let identity = self
+            .identity
+            .get_or_try_init(|| async {
+                let resp = self.sts.get_caller_identity().send().await?;
+                anyhow::Ok(resp.arn.unwrap_or_else(|| "unknown".to_string()))
+            })
+            .await;
+        match identity {
+            Ok(arn) => arn.clone(),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    pub async fn record(
+        &self,
+        instance_id: &str,
+        action: &str,
+        parameters: &[(&str, String)],
+        result: &Result<()>,
+    ) {
+        let record = AuditRecord {
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            identity: self.identity().await,
+            region: self.region.clone(),
+            instance_id: instance_id.to_string(),
+            action: action.to_string(),
+            parameters: parameters
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.clone()))
+                .collect(),
+            outcome: match result {
+                Ok(()) => "ok".to_string(),
+                Err(e) => format!("error: {}", e),
+            },
+        };
+        // The action itself already happened; a broken log must not turn it into a failure
+        let _ = append(&record).await;
+    }
+}
+
+async fn append(record: &AuditRecord) -> Result<()> {
+    let mut line = serde_json::to_string(record)?;
+    line.push('\n');
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(AppConfig::get_audit_log_path()?)
+        .await?;
+    file.write_all(line.as_bytes()).await?;
+    Ok(())
+}
+
+/// Parses the log, newest record first. Lines that don't parse are skipped.
+pub fn parse(content: &str) -> Vec<AuditRecord> {
+    let mut records: Vec<AuditRecord> = content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect();
+    records.reverse();
+    records
+}
+
+pub async fn load() -> Result<Vec<AuditRecord>> {
+    let path = AppConfig::get_audit_log_path()?;
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(parse(&tokio::fs::read_to_string(path).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(instance_id: &str, action: &str) -> AuditRecord {
+        AuditRecord {
+            timestamp: "2026-10-18T12:00:00+00:00".to_string(),
+            identity: "arn:aws:iam::123456789012:user/alice".to_string(),
+            region: "eu-west-1".to_string(),
+            instance_id: instance_id.to_string(),
+            action: action.to_string(),
+            parameters: BTreeMap::new(),
+            outcome: "ok".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_parse_skips_bad_lines_newest_first() {
+        let first = serde_json::to_string(&record("i-1", "stop")).unwrap();
+        let second = serde_json::to_string(&record("i-2", "start")).unwrap();
+        let content = format!("{}\nnot json\n{}\n", first, second);
+
+        let records = parse(&content);
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].instance_id, "i-2");
+        assert_eq!(records[1].instance_id, "i-1");
+    }
+
+    #[test]
+    fn test_matches_instance_and_action() {
+        let r = record("i-0abc", "resize");
+
+        assert!(r.matches("", None));
+        assert!(r.matches("0AB", Some("resize")));
+        assert!(!r.matches("0ab", Some("stop")));
+        assert!(!r.matches("i-9", None));
+    }
+}
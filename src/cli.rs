@@ -0,0 +1,63 @@
+use crate::config::Endpoints;
+use crate::recording::Traffic;
+use anyhow::Result;
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+#[derive(Parser)]
+#[command(version, about = "Manage EC2 instances from the terminal")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
+    /// Browse only; refuse every action that would change an instance
+    #[arg(long, global = true)]
+    pub read_only: bool,
+
+    /// Send EC2 requests here instead of AWS, e.g. to a local emulator
+    #[arg(long, global = true, env = "AWS_ENDPOINT_URL_EC2")]
+    pub ec2_endpoint_url: Option<String>,
+
+    /// Send Price List requests here instead of AWS
+    #[arg(long, global = true, env = "AWS_ENDPOINT_URL_PRICING")]
+    pub pricing_endpoint_url: Option<String>,
+
+    /// Run against a generated fleet with built-in prices; no AWS account needed
+    #[arg(long, conflicts_with_all = ["record", "replay"])]
+    pub demo: bool,
+
+    /// Save every EC2 and Pricing request and response of this session to FILE
+    #[arg(long, global = true, value_name = "FILE", conflicts_with = "replay")]
+    pub record: Option<PathBuf>,
+
+    /// Answer EC2 and Pricing requests from a recorded session instead of AWS
+    #[arg(long, global = true, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+}
+
+impl Cli {
+    pub fn endpoints(&self) -> Endpoints {
+        Endpoints {
+            ec2: self.ec2_endpoint_url.clone(),
+            pricing: self.pricing_endpoint_url.clone(),
+        }
+    }
+
+    pub fn traffic(&self) -> Result<Option<Traffic>> {
+        match (&self.record, &self.replay) {
+            (Some(path), _) => Ok(Some(Traffic::record(path)?)),
+            (None, Some(path)) => Ok(Some(Traffic::replay(path)?)),
+            (None, None) => Ok(None),
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Enforce the configured start/stop schedules without the TUI
+    Daemon {
+        /// Seconds between schedule checks
+        #[arg(long, default_value_t = 60)]
+        interval_seconds: u64,
+    },
+}
@@ -0,0 +1,273 @@
+use crate::ec2::InstanceInfo;
+use crate::idle::{IdleCandidate, IdleThresholds};
+use crate::rightsizing::UtilizationStats;
+use anyhow::Result;
+use aws_config::meta::region::RegionProviderChain;
+use aws_sdk_cloudwatch::Client;
+use aws_sdk_cloudwatch::types::{Dimension, Metric, MetricDataQuery, MetricStat, ScanBy};
+use std::collections::HashMap;
+use std::time::{Duration, SystemTime};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricsWindow {
+    OneHour,
+    SixHours,
+    OneDay,
+    OneWeek,
+}
+
+impl MetricsWindow {
+    pub fn next(self) -> Self {
+        match self {
+            MetricsWindow::OneHour => MetricsWindow::SixHours,
+            MetricsWindow::SixHours => MetricsWindow::OneDay,
+            MetricsWindow::OneDay => MetricsWindow::OneWeek,
+            MetricsWindow::OneWeek => MetricsWindow::OneHour,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            MetricsWindow::OneHour => "1h",
+            MetricsWindow::SixHours => "6h",
+            MetricsWindow::OneDay => "24h",
+            MetricsWindow::OneWeek => "7d",
+        }
+    }
+
+    fn duration(self) -> Duration {
+        match self {
+            MetricsWindow::OneHour => Duration::from_secs(3600),
+            MetricsWindow::SixHours => Duration::from_secs(6 * 3600),
+            MetricsWindow::OneDay => Duration::from_secs(24 * 3600),
+            MetricsWindow::OneWeek => Duration::from_secs(7 * 24 * 3600),
+        }
+    }
+
+    // Basic monitoring publishes every 5 minutes, so that is the finest useful period
+    fn period_seconds(self) -> i32 {
+        match self {
+            MetricsWindow::OneHour | MetricsWindow::SixHours => 300,
+            MetricsWindow::OneDay => 900,
+            MetricsWindow::OneWeek => 3600,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct InstanceMetrics {
+    pub instance_id: String,
+    pub cpu_utilization: Vec<f64>,
+    pub network_in: Vec<f64>,
+    pub network_out: Vec<f64>,
+    pub cpu_credit_balance: Option<Vec<f64>>, // Burstable types only
+}
+
+/// One series to fetch: (query id, namespace, metric name, statistic).
+pub type MetricQuery<'a> = (&'a str, &'a str, &'a str, &'a str);
+
+#[derive(Clone)]
+pub struct CloudWatchClient {
+    client: Client,
+}
+
+impl CloudWatchClient {
+    pub async fn new() -> Result<Self> {
+        let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
+        let config = aws_config::defaults(aws_config::BehaviorVersion::latest())
+            .region(region_provider)
+            .load()
+            .await;
+        let client = Client::new(&config);
+        Ok(Self { client })
+    }
+
+    pub async fn fetch_instance_metrics(
+        &self,
+        instance_id: &str,
+        window: MetricsWindow,
+        burstable: bool,
+    ) -> Result<InstanceMetrics> {
+        let mut queries: Vec<MetricQuery> = vec![
+            ("cpu", "AWS/EC2", "CPUUtilization", "Average"),
+            ("net_in", "AWS/EC2", "NetworkIn", "Sum"),
+            ("net_out", "AWS/EC2", "NetworkOut", "Sum"),
+        ];
+        if burstable {
+            queries.push(("credits", "AWS/EC2", "CPUCreditBalance", "Average"));
+        }
+
+        let mut series = self
+            .fetch_series(
+                instance_id,
+                &queries,
+                window.duration(),
+                window.period_seconds(),
+            )
+            .await?;
+
+        Ok(InstanceMetrics {
+            instance_id: instance_id.to_string(),
+            cpu_utilization: series.remove("cpu").unwrap_or_default(),
+            network_in: series.remove("net_in").unwrap_or_default(),
+            network_out: series.remove("net_out").unwrap_or_default(),
+            cpu_credit_balance: if burstable {
+                Some(series.remove("credits").unwrap_or_default())
+            } else {
+                None
+            },
+        })
+    }
+
+    /// Summarizes hourly CPU and (CloudWatch agent) memory utilization over the past `days`.
+    pub async fn fetch_utilization_stats(
+        &self,
+        instance_id: &str,
+        days: u32,
+    ) -> Result<UtilizationStats> {
+        let queries: [MetricQuery; 3] = [
+            ("cpu_avg", "AWS/EC2", "CPUUtilization", "Average"),
+            ("cpu_max", "AWS/EC2", "CPUUtilization", "Maximum"),
+            ("mem_max", "CWAgent", "mem_used_percent", "Maximum"),
+        ];
+        let series = self
+            .fetch_series(
+                instance_id,
+                &queries,
+                Duration::from_secs(days as u64 * 24 * 3600),
+                3600,
+            )
+            .await?;
+
+        let cpu_avg = series.get("cpu_avg").map(|v| v.as_slice()).unwrap_or(&[]);
+        if cpu_avg.is_empty() {
+            anyhow::bail!(
+                "No CPU datapoints for {} in the last {} days",
+                instance_id,
+                days
+            );
+        }
+        let cpu_max = series.get("cpu_max").map(|v| v.as_slice()).unwrap_or(&[]);
+
+        Ok(UtilizationStats {
+            days,
+            cpu_avg: cpu_avg.iter().sum::<f64>() / cpu_avg.len() as f64,
+            cpu_peak: percentile(cpu_max, 95.0).unwrap_or(0.0),
+            mem_peak: series
+                .get("mem_max")
+                .and_then(|v| v.iter().copied().reduce(f64::max)),
+        })
+    }
+
+    /// Returns a candidate if `instance` stayed below the idle thresholds for the whole window.
+    pub async fn check_idle(
+        &self,
+        instance: &InstanceInfo,
+        thresholds: &IdleThresholds,
+    ) -> Result<Option<IdleCandidate>> {
+        let queries: [MetricQuery; 3] = [
+            ("cpu_max", "AWS/EC2", "CPUUtilization", "Maximum"),
+            ("net_in", "AWS/EC2", "NetworkIn", "Sum"),
+            ("net_out", "AWS/EC2", "NetworkOut", "Sum"),
+        ];
+        let series = self
+            .fetch_series(
+                &instance.id,
+                &queries,
+                Duration::from_secs(thresholds.hours as u64 * 3600),
+                3600,
+            )
+            .await?;
+
+        let empty = Vec::new();
+        let cpu_max = series.get("cpu_max").unwrap_or(&empty);
+        let net_in = series.get("net_in").unwrap_or(&empty);
+        let net_out = series.get("net_out").unwrap_or(&empty);
+        let network: Vec<f64> = net_in.iter().zip(net_out).map(|(i, o)| i + o).collect();
+
+        Ok(
+            crate::idle::evaluate(cpu_max, &network, thresholds).map(|(max_cpu, max_net)| {
+                IdleCandidate {
+                    instance_id: instance.id.clone(),
+                    name: instance.name.clone(),
+                    instance_type: instance.instance_type.clone(),
+                    max_cpu,
+                    max_network_bytes: max_net,
+                    hours: thresholds.hours,
+                    marked: false,
+                }
+            }),
+        )
+    }
+
+    /// Fetches the given per-instance series over the trailing `lookback`, oldest point first.
+    /// Series without datapoints are returned empty.
+    pub async fn fetch_series(
+        &self,
+        instance_id: &str,
+        queries: &[MetricQuery<'_>],
+        lookback: Duration,
+        period_seconds: i32,
+    ) -> Result<HashMap<String, Vec<f64>>> {
+        let now = SystemTime::now();
+        let mut request = self
+            .client
+            .get_metric_data()
+            .start_time(aws_sdk_cloudwatch::primitives::DateTime::from(
+                now - lookback,
+            ))
+            .end_time(aws_sdk_cloudwatch::primitives::DateTime::from(now))
+            .scan_by(ScanBy::TimestampAscending);
+
+        for (id, namespace, metric_name, stat) in queries {
+            let metric = Metric::builder()
+                .namespace(*namespace)
+                .metric_name(*metric_name)
+                .dimensions(
+                    Dimension::builder()
+                        .name("InstanceId")
+                        .value(instance_id)
+                        .build()?,
+                )
+                .build();
+            request = request.metric_data_queries(
+                MetricDataQuery::builder()
+                    .id(*id)
+                    .metric_stat(
+                        MetricStat::builder()
+                            .metric(metric)
+                            .period(period_seconds)
+                            .stat(*stat)
+                            .build()?,
+                    )
+                    .build()?,
+            );
+        }
+
+        let mut series = HashMap::new();
+        let mut stream = request.into_paginator().send();
+        while let Some(resp) = stream.next().await {
+            let resp = resp?;
+            for result in resp.metric_data_results.unwrap_or_default() {
+                if let Some(id) = result.id {
+                    series
+                        .entry(id)
+                        .or_insert_with(Vec::new)
+                        .extend(result.values.unwrap_or_default());
+                }
+            }
+        }
+        Ok(series)
+    }
+}
+
+/// Nearest-rank percentile of `values`, or `None` if empty.
+pub fn percentile(values: &[f64], p: f64) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort_by(|a, b| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal));
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
+}
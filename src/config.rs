@@ -10,6 +10,89 @@ pub struct AppConfig {
     pub t_family_credit: Option<String>,
     pub default_instance_type: Option<String>,
     pub refresh_interval_seconds: Option<u64>,
+    pub spot_advisor_source: Option<String>,
+    pub rightsizing_lookback_days: Option<u32>,
+    pub idle_cpu_threshold_percent: Option<f64>,
+    pub idle_network_threshold_bytes: Option<f64>, // Per hour, in + out
+    pub idle_hours: Option<u32>,
+    pub schedules: Option<Vec<ScheduleRule>>,
+    pub resize_schedules: Option<Vec<ResizeScheduleRule>>,
+    pub policies: Option<Vec<PolicyRule>>,
+    pub read_only: Option<bool>,
+    pub login_command: Option<String>, // Run by the re-login key, "aws sso login" if unset
+    pub ec2_endpoint_url: Option<String>, // e.g. a local emulator; the AWS endpoint if unset
+    pub pricing_endpoint_url: Option<String>,
+    pub wait_interval_seconds: Option<u64>, // First pause between polls; doubles after each
+    pub wait_max_interval_seconds: Option<u64>,
+    pub wait_timeout_seconds: Option<u64>, // Running, stopped and terminated
+    pub status_check_timeout_seconds: Option<u64>,
+    pub wait_for_status_checks: Option<bool>, // Start and resize finish once both checks pass
+}
+
+/// Endpoint URL overrides for the SDK clients. `None` keeps the regular AWS endpoint.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Endpoints {
+    pub ec2: Option<String>,
+    pub pricing: Option<String>,
+}
+
+/// Picks instances by ID, Name tag or `key=value` tag. All given criteria must match.
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct InstanceSelector {
+    pub id: Option<String>,
+    pub name: Option<String>,
+    pub tag: Option<String>,
+}
+
+/// Starts the selected instances at `on` and stops them at `off`, both five-field cron
+/// expressions evaluated in `timezone` (an IANA name, UTC if unset).
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ScheduleRule {
+    pub name: Option<String>,
+    pub select: InstanceSelector,
+    pub timezone: Option<String>,
+    pub on: Option<String>,
+    pub off: Option<String>,
+}
+
+/// Resizes the selected instances to `instance_type` whenever `at` (a cron expression) fires.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResizeWindow {
+    pub at: String,
+    pub instance_type: String,
+}
+
+/// The target type at any moment is the one from the window that fired most recently.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ResizeScheduleRule {
+    pub name: Option<String>,
+    pub select: InstanceSelector,
+    pub timezone: Option<String>,
+    pub windows: Vec<ResizeWindow>,
+}
+
+/// Actions that policies can restrict.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PolicyAction {
+    Start,
+    Stop,
+    Hibernate,
+    Reboot,
+    Resize,
+    Protection,
+}
+
+/// For the selected instances, actions in `confirm` need a yes/no prompt, actions in
+/// `type_name` need the instance name typed back, and actions in `forbid` are refused.
+/// When several rules match an instance the strictest one wins.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PolicyRule {
+    pub name: Option<String>,
+    pub select: InstanceSelector,
+    pub confirm: Option<Vec<PolicyAction>>,
+    pub type_name: Option<Vec<PolicyAction>>,
+    pub forbid: Option<Vec<PolicyAction>>,
 }
 
 impl Default for AppConfig {
@@ -19,6 +102,23 @@ impl Default for AppConfig {
             t_family_credit: Some("standard".to_string()),
             default_instance_type: Some("t3a.nano".to_string()),
             refresh_interval_seconds: Some(15),
+            spot_advisor_source: Some(crate::spot_advisor::DEFAULT_SOURCE.to_string()),
+            rightsizing_lookback_days: Some(14),
+            idle_cpu_threshold_percent: Some(5.0),
+            idle_network_threshold_bytes: Some(5.0 * 1024.0 * 1024.0),
+            idle_hours: Some(72),
+            schedules: None,
+            resize_schedules: None,
+            policies: None,
+            read_only: None,
+            login_command: None,
+            ec2_endpoint_url: None,
+            pricing_endpoint_url: None,
+            wait_interval_seconds: Some(2),
+            wait_max_interval_seconds: Some(15),
+            wait_timeout_seconds: Some(300),
+            status_check_timeout_seconds: Some(900),
+            wait_for_status_checks: Some(false),
         }
     }
 }
@@ -40,12 +140,57 @@ impl AppConfig {
             if config.refresh_interval_seconds.is_none() {
                 config.refresh_interval_seconds = defaults.refresh_interval_seconds;
             }
+            if config.spot_advisor_source.is_none() {
+                config.spot_advisor_source = defaults.spot_advisor_source;
+            }
+            if config.rightsizing_lookback_days.is_none() {
+                config.rightsizing_lookback_days = defaults.rightsizing_lookback_days;
+            }
+            if config.idle_cpu_threshold_percent.is_none() {
+                config.idle_cpu_threshold_percent = defaults.idle_cpu_threshold_percent;
+            }
+            if config.idle_network_threshold_bytes.is_none() {
+                config.idle_network_threshold_bytes = defaults.idle_network_threshold_bytes;
+            }
+            if config.idle_hours.is_none() {
+                config.idle_hours = defaults.idle_hours;
+            }
+            if config.wait_interval_seconds.is_none() {
+                config.wait_interval_seconds = defaults.wait_interval_seconds;
+            }
+            if config.wait_max_interval_seconds.is_none() {
+                config.wait_max_interval_seconds = defaults.wait_max_interval_seconds;
+            }
+            if config.wait_timeout_seconds.is_none() {
+                config.wait_timeout_seconds = defaults.wait_timeout_seconds;
+            }
+            if config.status_check_timeout_seconds.is_none() {
+                config.status_check_timeout_seconds = defaults.status_check_timeout_seconds;
+            }
+            if config.wait_for_status_checks.is_none() {
+                config.wait_for_status_checks = defaults.wait_for_status_checks;
+            }
             Ok(config)
         } else {
             Ok(Self::default())
         }
     }
 
+    /// The configured endpoints, with `overrides` from the command line or environment
+    /// taking precedence.
+    pub fn endpoints(&self, overrides: &Endpoints) -> Endpoints {
+        Endpoints {
+            ec2: overrides
+                .ec2
+                .clone()
+                .or_else(|| self.ec2_endpoint_url.clone()),
+            pricing: overrides
+                .pricing
+                .clone()
+                .or_else(|| self.pricing_endpoint_url.clone()),
+        }
+    }
+
     pub async fn save(&self) -> Result<()> {
         let path = Self::get_config_path()?;
         let content = toml::to_string_pretty(self)?;
@@ -60,4 +205,24 @@ impl AppConfig {
             anyhow::bail!("Could not determine home directory")
         }
     }
+
+    pub fn get_schedule_log_path() -> Result<PathBuf> {
+        if let Some(user_dirs) = UserDirs::new() {
+            Ok(user_dirs
+                .home_dir()
+                .join(".ec2-instance-manager-schedule.log"))
+        } else {
+            anyhow::bail!("Could not determine home directory")
+        }
+    }
+
+    pub fn get_audit_log_path() -> Result<PathBuf> {
+        if let Some(user_dirs) = UserDirs::new() {
+            Ok(user_dirs
+                .home_dir()
+                .join(".ec2-instance-manager-audit.jsonl"))
+        } else {
+            anyhow::bail!("Could not determine home directory")
+        }
+    }
 }
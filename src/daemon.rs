@@ -0,0 +1,283 @@
+use crate::app::AppEvent;
+use crate::config::{AppConfig, Endpoints, PolicyAction, PolicyRule};
+use crate::ec2::{Ec2Client, InstanceInfo, Waiters};
+use crate::operations::Cancel;
+use crate::policy::Requirement;
+use crate::recording::Traffic;
+use crate::schedule::{CompiledResizeRule, CompiledRule, ScheduledAction};
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+
+// Actions carried out more than this long after their scheduled time are logged as missed
+const MISSED_AFTER_MINUTES: i64 = 10;
+
+/// Appends a timestamped line to the schedule log and echoes it to stderr.
+pub async fn log(msg: &str) {
+    let line = format!("{} {}\n", Utc::now().to_rfc3339(), msg);
+    eprint!("{}", line);
+    if let Ok(path) = AppConfig::get_schedule_log_path()
+        && let Ok(mut file) = tokio::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .await
+    {
+        let _ = file.write_all(line.as_bytes()).await;
+    }
+}
+
+struct Daemon {
+    client: Ec2Client,
+    rules: Vec<CompiledRule>,
+    resize_rules: Vec<CompiledResizeRule>,
+    policies: Vec<PolicyRule>,
+    credit_spec: String,
+    // Latest scheduled time handled per (rule, instance)
+    handled: HashMap<(String, String), DateTime<Utc>>,
+    // Instances with a scheduled resize in flight
+    busy: Arc<Mutex<HashSet<String>>>,
+}
+
+/// Enforces the configured schedules until interrupted.
+pub async fn run(
+    config: AppConfig,
+    endpoints: Endpoints,
+    traffic: Option<Traffic>,
+    interval: Duration,
+) -> Result<()> {
+    let rules = CompiledRule::compile_all(config.schedules.as_deref().unwrap_or(&[]))?;
+    let resize_rules =
+        CompiledResizeRule::compile_all(config.resize_schedules.as_deref().unwrap_or(&[]))?;
+    if rules.is_empty() && resize_rules.is_empty() {
+        anyhow::bail!("No schedules configured");
+    }
+    log(&format!(
+        "Daemon started with {} schedule(s) and {} resize schedule(s)",
+        rules.len(),
+        resize_rules.len()
+    ))
+    .await;
+
+    let mut daemon = Daemon {
+        client: Ec2Client::new(endpoints.ec2.as_deref(), traffic.as_ref())
+            .await?
+            .with_waiters(Waiters::from_config(&config)),
+        rules,
+        resize_rules,
+        policies: config.policies.clone().unwrap_or_default(),
+        credit_spec: config
+            .t_family_credit
+            .clone()
+            .unwrap_or("standard".to_string()),
+        handled: HashMap::new(),
+        busy: Arc::new(Mutex::new(HashSet::new())),
+    };
+
+    let mut ticker = tokio::time::interval(interval);
+    loop {
+        tokio::select! {
+            _ = ticker.tick() => {
+                if let Err(e) = daemon.enforce().await {
+                    log(&format!("Refresh failed: {}", e)).await;
+                }
+            }
+            _ = tokio::signal::ctrl_c() => {
+                log("Daemon stopped").await;
+                return Ok(());
+            }
+        }
+    }
+}
+
+impl Daemon {
+    fn is_busy(&self, instance_id: &str) -> bool {
+        self.busy.lock().unwrap().contains(instance_id)
+    }
+
+    /// Why the policies keep `action` on `instance` from running unattended, if they do.
+    /// Anything stricter than no confirmation needs a person at the keyboard.
+    fn policy_block(&self, instance: &InstanceInfo, action: PolicyAction) -> Option<String> {
+        match crate::policy::requirement(&self.policies, instance, action) {
+            Requirement::None => None,
+            Requirement::Forbidden => Some("forbidden by policy".to_string()),
+            req => Some(format!("policy requires {}", req.label())),
+        }
+    }
+
+    async fn enforce(&mut self) -> Result<()> {
+        let now = Utc::now();
+        let instances = self.client.list_instances().await?;
+        self.enforce_power(&instances, now).await;
+        self.enforce_resize(&instances, now).await;
+        Ok(())
+    }
+
+    async fn enforce_power(&mut self, instances: &[InstanceInfo], now: DateTime<Utc>) {
+        for rule in &self.rules {
+            let Some((action, at)) = rule.last_action(now) else {
+                continue;
+            };
+            for instance in instances.iter().filter(|i| rule.select.matches(i)) {
+                let key = (rule.name.clone(), instance.id.clone());
+                if self.handled.get(&key).is_some_and(|h| *h >= at) || self.is_busy(&instance.id) {
+                    continue;
+                }
+
+                let kind = match action {
+                    ScheduledAction::Start => PolicyAction::Start,
+                    _ => PolicyAction::Stop,
+                };
+                if matches!(
+                    (&action, instance.state.as_str()),
+                    (ScheduledAction::Start, "stopped") | (ScheduledAction::Stop, "running")
+                ) && let Some(reason) = self.policy_block(instance, kind)
+                {
+                    self.handled.insert(key, at);
+                    let what = describe(&action, instance, &rule.name, at);
+                    log(&format!("SKIPPED {}: {}", what, reason)).await;
+                    continue;
+                }
+
+                let result = match (&action, instance.state.as_str()) {
+                    (ScheduledAction::Start, "stopped") => {
+                        Some(self.client.start_instance(&instance.id).await)
+                    }
+                    (ScheduledAction::Stop, "running") => {
+                        match self.client.get_protection(&instance.id).await {
+                            Ok(p) if p.stop => {
+                                self.handled.insert(key, at);
+                                let what = describe(&action, instance, &rule.name, at);
+                                log(&format!("SKIPPED {}: stop protection enabled", what)).await;
+                                continue;
+                            }
+                            _ => Some(self.client.stop_instance(&instance.id).await),
+                        }
+                    }
+                    // Still transitioning the other way; try again next tick
+                    (ScheduledAction::Start, "stopping") | (ScheduledAction::Stop, "pending") => {
+                        continue;
+                    }
+                    // Already in (or heading to) the desired state, or terminated
+                    _ => None,
+                };
+                self.handled.insert(key, at);
+
+                if let Some(result) = result {
+                    let what = describe(&action, instance, &rule.name, at);
+                    let late = now - at > chrono::Duration::minutes(MISSED_AFTER_MINUTES);
+                    match result {
+                        Ok(()) if late => log(&format!("MISSED {}; enforced late", what)).await,
+                        Ok(()) => log(&format!("OK {}", what)).await,
+                        Err(e) => log(&format!("FAILED {}: {}", what, e)).await,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Starts the resize pipeline for every selected instance not yet on its window's type.
+    /// Instances that are mid-transition or already being resized are retried next tick.
+    async fn enforce_resize(&mut self, instances: &[InstanceInfo], now: DateTime<Utc>) {
+        for rule in &self.resize_rules {
+            let Some((target, at)) = rule.current_target(now) else {
+                continue;
+            };
+            for instance in instances.iter().filter(|i| rule.select.matches(i)) {
+                let key = (rule.name.clone(), instance.id.clone());
+                if self.handled.get(&key).is_some_and(|h| *h >= at) {
+                    continue;
+                }
+                if instance.instance_type == target {
+                    self.handled.insert(key, at);
+                    continue;
+                }
+                if self.is_busy(&instance.id)
+                    || !matches!(instance.state.as_str(), "running" | "stopped")
+                {
+                    continue;
+                }
+                self.handled.insert(key, at);
+                let what = describe(
+                    &ScheduledAction::Resize(target.clone()),
+                    instance,
+                    &rule.name,
+                    at,
+                );
+                if let Some(reason) = self.policy_block(instance, PolicyAction::Resize) {
+                    log(&format!("SKIPPED {}: {}", what, reason)).await;
+                    continue;
+                }
+                self.busy.lock().unwrap().insert(instance.id.clone());
+
+                let late = now - at > chrono::Duration::minutes(MISSED_AFTER_MINUTES);
+                let client = self.client.clone();
+                let busy = self.busy.clone();
+                let instance_id = instance.id.clone();
+                let target = target.clone();
+                let credit_spec = self.credit_spec.clone();
+                // Keep stopped instances stopped, e.g. when a stop schedule already ran
+                let restart = instance.state == "running";
+
+                tokio::spawn(async move {
+                    let (tx, mut rx) = mpsc::channel(10);
+                    let errors = tokio::spawn(async move {
+                        let mut errors = Vec::new();
+                        while let Some(event) = rx.recv().await {
+                            match event {
+                                AppEvent::Error(e) => errors.push(e),
+                                AppEvent::Failed(e) => errors.push(format!("{} ({})", e, e.detail)),
+                                _ => {}
+                            }
+                        }
+                        errors
+                    });
+
+                    let ok = crate::actions::resize_instance(
+                        &client,
+                        &instance_id,
+                        &target,
+                        &credit_spec,
+                        restart,
+                        &Cancel::default(),
+                        &tx,
+                    )
+                    .await;
+                    drop(tx);
+                    let errors = errors.await.unwrap_or_default();
+
+                    if !ok {
+                        log(&format!("FAILED {}: {}", what, errors.join("; "))).await;
+                    } else if !errors.is_empty() {
+                        log(&format!("OK {} with errors: {}", what, errors.join("; "))).await;
+                    } else if late {
+                        log(&format!("MISSED {}; enforced late", what)).await;
+                    } else {
+                        log(&format!("OK {}", what)).await;
+                    }
+                    busy.lock().unwrap().remove(&instance_id);
+                });
+            }
+        }
+    }
+}
+
+fn describe(
+    action: &ScheduledAction,
+    instance: &InstanceInfo,
+    rule_name: &str,
+    at: DateTime<Utc>,
+) -> String {
+    format!(
+        "{} {} ({}) for schedule '{}' at {}",
+        action.label(),
+        instance.id,
+        instance.name.as_deref().unwrap_or("-"),
+        rule_name,
+        at.to_rfc3339()
+    )
+}
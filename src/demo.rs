@@ -0,0 +1,228 @@
+use crate::ec2::{InstanceInfo, InstanceTypeInfo, Protection, SpotRequestInfo};
+use crate::fake::{self, FakeFleet};
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub const REGION: &str = "us-east-1";
+
+// Name, architecture, vCPUs, memory (MiB), on-demand USD per hour
+const CATALOG: &[(&str, &str, i32, i64, f64)] = &[
+    ("t3.nano", "x86_64", 2, 512, 0.0052),
+    ("t3.micro", "x86_64", 2, 1024, 0.0104),
+    ("t3.small", "x86_64", 2, 2048, 0.0208),
+    ("t3.medium", "x86_64", 2, 4096, 0.0416),
+    ("t3.large", "x86_64", 2, 8192, 0.0832),
+    ("t3.xlarge", "x86_64", 4, 16384, 0.1664),
+    ("t3a.nano", "x86_64", 2, 512, 0.0047),
+    ("t3a.micro", "x86_64", 2, 1024, 0.0094),
+    ("t3a.small", "x86_64", 2, 2048, 0.0188),
+    ("t3a.medium", "x86_64", 2, 4096, 0.0376),
+    ("t3a.large", "x86_64", 2, 8192, 0.0752),
+    ("t4g.nano", "arm64", 2, 512, 0.0042),
+    ("t4g.micro", "arm64", 2, 1024, 0.0084),
+    ("t4g.small", "arm64", 2, 2048, 0.0168),
+    ("t4g.medium", "arm64", 2, 4096, 0.0336),
+    ("t4g.large", "arm64", 2, 8192, 0.0672),
+    ("m5.large", "x86_64", 2, 8192, 0.096),
+    ("m5.xlarge", "x86_64", 4, 16384, 0.192),
+    ("m5.2xlarge", "x86_64", 8, 32768, 0.384),
+    ("m6g.large", "arm64", 2, 8192, 0.077),
+    ("m6g.xlarge", "arm64", 4, 16384, 0.154),
+    ("m7i.large", "x86_64", 2, 8192, 0.1008),
+    ("m7i.xlarge", "x86_64", 4, 16384, 0.2016),
+    ("c5.large", "x86_64", 2, 4096, 0.085),
+    ("c5.xlarge", "x86_64", 4, 8192, 0.17),
+    ("c6g.large", "arm64", 2, 4096, 0.068),
+    ("c6g.xlarge", "arm64", 4, 8192, 0.136),
+    ("r5.large", "x86_64", 2, 16384, 0.126),
+    ("r5.xlarge", "x86_64", 4, 32768, 0.252),
+    ("r6g.large", "arm64", 2, 16384, 0.1008),
+];
+
+// Spot prices are a flat fraction of on-demand
+const SPOT_DISCOUNT: f64 = 0.35;
+
+fn instance_types() -> Vec<InstanceTypeInfo> {
+    CATALOG
+        .iter()
+        .map(|&(name, arch, vcpu, memory_mib, _)| InstanceTypeInfo {
+            name: name.to_string(),
+            architectures: vec![arch.to_string()],
+            vcpu: Some(vcpu),
+            memory_mib: Some(memory_mib),
+        })
+        .collect()
+}
+
+pub fn on_demand_prices() -> HashMap<String, f64> {
+    CATALOG
+        .iter()
+        .map(|&(name, _, _, _, price)| (name.to_string(), price))
+        .collect()
+}
+
+fn spot_prices() -> HashMap<String, f64> {
+    CATALOG
+        .iter()
+        .map(|&(name, _, _, _, price)| (name.to_string(), price * SPOT_DISCOUNT))
+        .collect()
+}
+
+fn instance_id(n: u32) -> String {
+    format!("i-0d3e40{:011x}", n)
+}
+
+/// `az` is the zone letter, `hours_up` how long ago it was launched.
+fn demo_instance(
+    n: u32,
+    name: &str,
+    instance_type: &str,
+    state: &str,
+    az: char,
+    hours_up: i64,
+) -> InstanceInfo {
+    let mut info = fake::instance(&instance_id(n), instance_type, state);
+    let arch = CATALOG
+        .iter()
+        .find(|t| t.0 == instance_type)
+        .map(|t| t.1)
+        .unwrap_or("x86_64");
+    info.name = Some(name.to_string());
+    info.tags.insert("Name".to_string(), name.to_string());
+    info.architecture = Some(arch.to_string());
+    info.availability_zone = Some(format!("{}{}", REGION, az));
+    if state == "running" {
+        info.public_ip = Some(format!("203.0.113.{}", 10 + n));
+    }
+    info.launch_time = Some(aws_sdk_ec2::primitives::DateTime::from_secs(
+        chrono::Utc::now().timestamp() - hours_up * 3600,
+    ));
+    info
+}
+
+fn spot(mut info: InstanceInfo, request_id: &str) -> InstanceInfo {
+    info.instance_lifecycle = Some("spot".to_string());
+    info.spot_instance_request_id = Some(request_id.to_string());
+    info
+}
+
+fn spot_request(id: &str, request_type: &str) -> SpotRequestInfo {
+    SpotRequestInfo {
+        id: id.to_string(),
+        request_type: Some(request_type.to_string()),
+        state: Some("active".to_string()),
+        status_code: Some("fulfilled".to_string()),
+        status_message: Some("Your spot request is fulfilled.".to_string()),
+        max_price: None,
+        interruption_behavior: Some(
+            if request_type == "persistent" {
+                "stop"
+            } else {
+                "terminate"
+            }
+            .to_string(),
+        ),
+    }
+}
+
+/// A mixed fleet across AZs, architectures, lifecycles and states, with realistic latencies.
+pub fn fleet() -> FakeFleet {
+    let mut dev_box = demo_instance(9, "dev-box", "t3.large", "stopped", 'a', 0);
+    dev_box.hibernation_configured = true;
+    dev_box.state_reason = Some("Client.UserInitiatedHibernate".to_string());
+    let mut legacy = demo_instance(12, "legacy-app", "m5.large", "running", 'c', 2150);
+    legacy.hibernation_configured = true;
+
+    FakeFleet::new()
+        .with_latency(Duration::from_millis(400))
+        .with_delays(Duration::from_secs(8), Duration::from_secs(12))
+        .with_check_delay(Duration::from_secs(20))
+        .with_instance_types(instance_types())
+        .with_spot_prices(spot_prices())
+        .with_instance(demo_instance(1, "web-1", "t3.medium", "running", 'a', 310))
+        .with_instance(demo_instance(2, "web-2", "t3.medium", "running", 'b', 310))
+        .with_instance(demo_instance(3, "api-1", "m6g.large", "running", 'a', 96))
+        .with_instance(demo_instance(4, "api-2", "m6g.large", "stopped", 'c', 0))
+        .with_instance(spot(
+            demo_instance(5, "worker-spot", "c5.large", "running", 'b', 20),
+            "sir-demo0001",
+        ))
+        .with_instance(spot(
+            demo_instance(6, "batch-spot", "c6g.xlarge", "running", 'c', 3),
+            "sir-demo0002",
+        ))
+        .with_instance(demo_instance(
+            7,
+            "db-replica",
+            "r5.large",
+            "running",
+            'a',
+            1200,
+        ))
+        .with_instance(demo_instance(
+            8,
+            "analytics",
+            "r6g.large",
+            "stopped",
+            'b',
+            0,
+        ))
+        .with_instance(dev_box)
+        .with_instance(demo_instance(
+            10,
+            "ci-runner",
+            "m5.xlarge",
+            "pending",
+            'c',
+            0,
+        ))
+        .with_instance(demo_instance(
+            11,
+            "build-agent",
+            "t4g.small",
+            "stopping",
+            'a',
+            5,
+        ))
+        .with_instance(legacy)
+        .with_instance(demo_instance(
+            13, "bastion", "t3a.nano", "running", 'b', 4000,
+        ))
+        .with_impaired(&instance_id(12))
+        .with_spot_request(spot_request("sir-demo0001", "persistent"))
+        .with_spot_request(spot_request("sir-demo0002", "one-time"))
+        .with_protection(
+            &instance_id(7),
+            Protection {
+                stop: true,
+                termination: true,
+            },
+        )
+        .with_protection(
+            &instance_id(13),
+            Protection {
+                stop: false,
+                termination: true,
+            },
+        )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec2::Ec2Backend;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_every_demo_instance_has_a_catalog_price() {
+        let prices = on_demand_prices();
+        let instances = fleet().list_instances().await.unwrap();
+        assert_eq!(instances.len(), 13);
+        for info in &instances {
+            assert!(
+                prices.contains_key(&info.instance_type),
+                "{}",
+                info.instance_type
+            );
+        }
+    }
+}
@@ -1,6 +1,15 @@
+use crate::audit::Auditor;
+use crate::config::AppConfig;
+use crate::errors::api_error;
+use crate::operations::Cancel;
+use crate::recording::Traffic;
 use anyhow::Result;
+use async_trait::async_trait;
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_ec2::Client;
+use aws_sdk_ec2::error::{ProvideErrorMetadata, SdkError};
+use std::sync::Arc;
+use std::time::Duration;
 
 #[derive(Debug, Clone)]
 pub struct InstanceInfo {
@@ -12,6 +21,123 @@ pub struct InstanceInfo {
     pub availability_zone: Option<String>,
     pub public_ip: Option<String>,
     pub launch_time: Option<aws_sdk_ec2::primitives::DateTime>,
+    pub instance_lifecycle: Option<String>,
+    pub spot_instance_request_id: Option<String>,
+    pub tags: std::collections::HashMap<String, String>,
+    pub hibernation_configured: bool,
+    pub state_reason: Option<String>, // State reason code, e.g. "Client.UserInitiatedHibernate"
+}
+
+impl InstanceInfo {
+    pub fn is_spot(&self) -> bool {
+        self.instance_lifecycle.as_deref() == Some("spot")
+    }
+
+    /// Stopped by a hibernate request, so RAM contents will be restored on start.
+    pub fn is_hibernated(&self) -> bool {
+        self.state == "stopped"
+            && self.state_reason.as_deref() == Some("Client.UserInitiatedHibernate")
+    }
+}
+
+/// The `disableApiStop` and `disableApiTermination` attributes of an instance.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Protection {
+    pub stop: bool,
+    pub termination: bool,
+}
+
+/// Results of the system, instance and attached EBS checks, e.g. "ok" or "initializing".
+/// Instances that are not running report "not-applicable".
+#[derive(Debug, Clone, PartialEq)]
+pub struct StatusChecks {
+    pub system: String,
+    pub instance: String,
+    pub ebs: String,
+}
+
+impl StatusChecks {
+    /// Both reachability checks passed. EBS is left out, as not every instance reports it.
+    pub fn ok(&self) -> bool {
+        self.system == "ok" && self.instance == "ok"
+    }
+
+    /// Names of the checks that failed, e.g. `["system", "EBS"]`.
+    pub fn impaired(&self) -> Vec<&'static str> {
+        [
+            ("system", &self.system),
+            ("instance", &self.instance),
+            ("EBS", &self.ebs),
+        ]
+        .into_iter()
+        .filter(|(_, status)| *status == "impaired")
+        .map(|(name, _)| name)
+        .collect()
+    }
+}
+
+fn status_checks_of(status: &aws_sdk_ec2::types::InstanceStatus) -> StatusChecks {
+    let summary = |s: Option<&aws_sdk_ec2::types::SummaryStatus>| {
+        s.map(|s| s.as_str().to_string())
+            .unwrap_or_else(|| "not-applicable".to_string())
+    };
+    StatusChecks {
+        system: summary(status.system_status().and_then(|s| s.status())),
+        instance: summary(status.instance_status().and_then(|s| s.status())),
+        ebs: summary(status.attached_ebs_status().and_then(|s| s.status())),
+    }
+}
+
+/// Outcome of an EC2 request sent with `DryRun` set.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Permission {
+    Allowed,
+    Denied,
+    Unknown(String), // Any other error, e.g. the instance is in the wrong state
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PermissionCheck {
+    pub operation: &'static str,
+    pub permission: Permission,
+}
+
+/// `DryRunOperation` means the call would have been authorized, `UnauthorizedOperation`
+/// that it would not.
+fn dry_run_permission<T, E: ProvideErrorMetadata, R>(
+    result: std::result::Result<T, SdkError<E, R>>,
+) -> Permission {
+    match result {
+        Ok(_) => Permission::Allowed,
+        Err(e) => match e.code() {
+            Some("DryRunOperation") => Permission::Allowed,
+            Some("UnauthorizedOperation") => Permission::Denied,
+            _ => Permission::Unknown(
+                e.message()
+                    .map(|m| m.to_string())
+                    .unwrap_or_else(|| e.to_string()),
+            ),
+        },
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotRequestInfo {
+    pub id: String,
+    pub request_type: Option<String>,
+    pub state: Option<String>,
+    pub status_code: Option<String>,
+    pub status_message: Option<String>,
+    pub max_price: Option<String>,
+    pub interruption_behavior: Option<String>,
+}
+
+impl SpotRequestInfo {
+    /// Only persistent requests can be stopped and started again; one-time requests are
+    /// closed once their instance is interrupted or terminated.
+    pub fn is_persistent(&self) -> bool {
+        self.request_type.as_deref() == Some("persistent")
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -22,31 +148,73 @@ pub struct InstanceTypeInfo {
     pub memory_mib: Option<i64>,
 }
 
-#[derive(Clone)]
-pub struct Ec2Client {
-    client: Client,
+/// The EC2 calls the app relies on. `SdkEc2` sends them to AWS; tests swap in an
+/// in-memory fleet.
+#[async_trait]
+pub trait Ec2Backend: Send + Sync {
+    async fn list_instances(&self) -> Result<Vec<InstanceInfo>>;
+    /// Current state name of a single instance, e.g. "stopping".
+    async fn instance_state(&self, instance_id: &str) -> Result<String>;
+    async fn status_checks(&self, instance_id: &str) -> Result<StatusChecks>;
+    /// Status checks of every running instance, by instance ID.
+    async fn list_status_checks(&self) -> Result<std::collections::HashMap<String, StatusChecks>>;
+    async fn describe_spot_request(&self, request_id: &str) -> Result<SpotRequestInfo>;
+    async fn stop_instance(&self, instance_id: &str, force: bool) -> Result<()>;
+    async fn hibernate_instance(&self, instance_id: &str) -> Result<()>;
+    async fn start_instance(&self, instance_id: &str) -> Result<()>;
+    async fn reboot_instance(&self, instance_id: &str) -> Result<()>;
+    async fn get_protection(&self, instance_id: &str) -> Result<Protection>;
+    async fn set_stop_protection(&self, instance_id: &str, enabled: bool) -> Result<()>;
+    async fn set_termination_protection(&self, instance_id: &str, enabled: bool) -> Result<()>;
+    async fn modify_instance_type(&self, instance_id: &str, new_type: &str) -> Result<()>;
+    async fn modify_credit_specification(&self, instance_id: &str, credit_spec: &str)
+    -> Result<()>;
+    async fn check_permissions(
+        &self,
+        instance_id: &str,
+        instance_type: &str,
+        credit_spec: &str,
+    ) -> Vec<PermissionCheck>;
+    async fn get_instance_types(&self) -> Result<Vec<InstanceTypeInfo>>;
+    async fn fetch_all_spot_prices(
+        &self,
+        az: &str,
+    ) -> Result<std::collections::HashMap<String, f64>>;
 }
 
-impl Ec2Client {
-    pub async fn new() -> Result<Self> {
-        let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
-        #[allow(deprecated)]
-        let config = aws_config::from_env().region(region_provider).load().await;
-        let client = Client::new(&config);
-        Ok(Self { client })
-    }
+pub struct SdkEc2 {
+    client: Client,
+}
 
-    #[allow(dead_code)]
-    pub fn new_mock() -> Self {
-        let config = aws_sdk_ec2::Config::builder()
-            .behavior_version(aws_config::BehaviorVersion::latest())
-            .build();
-        let client = Client::from_conf(config);
-        Self { client }
+impl SdkEc2 {
+    pub fn new(
+        config: &aws_config::SdkConfig,
+        endpoint_url: Option<&str>,
+        traffic: Option<&Traffic>,
+    ) -> Result<Self> {
+        let mut builder = aws_sdk_ec2::config::Builder::from(config);
+        builder.set_endpoint_url(endpoint_url.map(str::to_string));
+        if let Some(traffic) = traffic {
+            builder.set_http_client(Some(traffic.http_client("ec2", config.http_client())?));
+            if let Some(credentials) = traffic.credentials() {
+                builder.set_credentials_provider(Some(credentials));
+            }
+        }
+        Ok(Self {
+            client: Client::from_conf(builder.build()),
+        })
     }
+}
 
-    pub async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
-        let resp = self.client.describe_instances().send().await?;
+#[async_trait]
+impl Ec2Backend for SdkEc2 {
+    async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+        let resp = self
+            .client
+            .describe_instances()
+            .send()
+            .await
+            .map_err(api_error)?;
         let mut instances = Vec::new();
 
         for reservation in resp.reservations.unwrap_or_default() {
@@ -63,12 +231,14 @@ impl Ec2Client {
                     .map(|t| t.as_str().to_string())
                     .unwrap_or_else(|| "unknown".to_string());
 
-                let name = instance
+                let tags: std::collections::HashMap<String, String> = instance
                     .tags
                     .unwrap_or_default()
-                    .iter()
-                    .find(|t| t.key.as_deref() == Some("Name"))
-                    .and_then(|t| t.value.clone());
+                    .into_iter()
+                    .filter_map(|t| Some((t.key?, t.value.unwrap_or_default())))
+                    .collect();
+
+                let name = tags.get("Name").cloned();
 
                 let architecture = instance.architecture.map(|a| a.as_str().to_string());
 
@@ -76,6 +246,16 @@ impl Ec2Client {
 
                 let public_ip = instance.public_ip_address;
 
+                let instance_lifecycle =
+                    instance.instance_lifecycle.map(|l| l.as_str().to_string());
+
+                let hibernation_configured = instance
+                    .hibernation_options
+                    .and_then(|h| h.configured)
+                    .unwrap_or(false);
+
+                let state_reason = instance.state_reason.and_then(|r| r.code);
+
                 instances.push(InstanceInfo {
                     id,
                     name,
@@ -85,63 +265,205 @@ impl Ec2Client {
                     availability_zone,
                     public_ip,
                     launch_time: instance.launch_time,
+                    instance_lifecycle,
+                    spot_instance_request_id: instance.spot_instance_request_id,
+                    tags,
+                    hibernation_configured,
+                    state_reason,
                 });
             }
         }
         Ok(instances)
     }
 
-    pub async fn stop_instance(&self, instance_id: &str) -> Result<()> {
+    async fn describe_spot_request(&self, request_id: &str) -> Result<SpotRequestInfo> {
+        let resp = self
+            .client
+            .describe_spot_instance_requests()
+            .spot_instance_request_ids(request_id)
+            .send()
+            .await
+            .map_err(api_error)?;
+        let request = resp
+            .spot_instance_requests
+            .unwrap_or_default()
+            .into_iter()
+            .next()
+            .ok_or_else(|| anyhow::anyhow!("Spot request {} not found", request_id))?;
+
+        Ok(SpotRequestInfo {
+            id: request_id.to_string(),
+            request_type: request.r#type.map(|t| t.as_str().to_string()),
+            state: request.state.map(|s| s.as_str().to_string()),
+            status_code: request.status.as_ref().and_then(|s| s.code.clone()),
+            status_message: request.status.and_then(|s| s.message),
+            max_price: request.spot_price,
+            interruption_behavior: request
+                .instance_interruption_behavior
+                .map(|b| b.as_str().to_string()),
+        })
+    }
+
+    async fn get_protection(&self, instance_id: &str) -> Result<Protection> {
+        // describe_instance_attribute returns a single attribute per call
+        let stop = self
+            .client
+            .describe_instance_attribute()
+            .instance_id(instance_id)
+            .attribute(aws_sdk_ec2::types::InstanceAttributeName::DisableApiStop)
+            .send()
+            .await
+            .map_err(api_error)?;
+        let termination = self
+            .client
+            .describe_instance_attribute()
+            .instance_id(instance_id)
+            .attribute(aws_sdk_ec2::types::InstanceAttributeName::DisableApiTermination)
+            .send()
+            .await
+            .map_err(api_error)?;
+
+        Ok(Protection {
+            stop: stop.disable_api_stop.and_then(|v| v.value).unwrap_or(false),
+            termination: termination
+                .disable_api_termination
+                .and_then(|v| v.value)
+                .unwrap_or(false),
+        })
+    }
+
+    async fn instance_state(&self, instance_id: &str) -> Result<String> {
+        let resp = self
+            .client
+            .describe_instances()
+            .instance_ids(instance_id)
+            .send()
+            .await
+            .map_err(api_error)?;
+        Ok(resp
+            .reservations
+            .unwrap_or_default()
+            .first()
+            .and_then(|r| r.instances.as_ref())
+            .and_then(|i| i.first())
+            .and_then(|i| i.state.as_ref())
+            .and_then(|s| s.name.as_ref())
+            .map(|n| n.as_str().to_string())
+            .unwrap_or_else(|| "unknown".to_string()))
+    }
+
+    async fn status_checks(&self, instance_id: &str) -> Result<StatusChecks> {
+        let resp = self
+            .client
+            .describe_instance_status()
+            .instance_ids(instance_id)
+            .include_all_instances(true)
+            .send()
+            .await
+            .map_err(api_error)?;
+        Ok(resp
+            .instance_statuses()
+            .first()
+            .map(status_checks_of)
+            .unwrap_or_else(|| StatusChecks {
+                system: "not-applicable".to_string(),
+                instance: "not-applicable".to_string(),
+                ebs: "not-applicable".to_string(),
+            }))
+    }
+
+    async fn list_status_checks(&self) -> Result<std::collections::HashMap<String, StatusChecks>> {
+        let mut checks = std::collections::HashMap::new();
+        let mut stream = self
+            .client
+            .describe_instance_status()
+            .into_paginator()
+            .send();
+
+        while let Some(resp) = stream.next().await {
+            let resp = resp.map_err(api_error)?;
+            for status in resp.instance_statuses() {
+                if let Some(id) = status.instance_id() {
+                    checks.insert(id.to_string(), status_checks_of(status));
+                }
+            }
+        }
+        Ok(checks)
+    }
+
+    async fn stop_instance(&self, instance_id: &str, force: bool) -> Result<()> {
         self.client
             .stop_instances()
             .instance_ids(instance_id)
+            .set_force(force.then_some(true))
             .send()
-            .await?;
+            .await
+            .map_err(api_error)?;
         Ok(())
     }
 
-    pub async fn start_instance(&self, instance_id: &str) -> Result<()> {
+    async fn hibernate_instance(&self, instance_id: &str) -> Result<()> {
         self.client
-            .start_instances()
+            .stop_instances()
             .instance_ids(instance_id)
+            .hibernate(true)
             .send()
-            .await?;
+            .await
+            .map_err(api_error)?;
         Ok(())
     }
 
-    pub async fn reboot_instance(&self, instance_id: &str) -> Result<()> {
+    async fn start_instance(&self, instance_id: &str) -> Result<()> {
         self.client
-            .reboot_instances()
+            .start_instances()
             .instance_ids(instance_id)
             .send()
-            .await?;
+            .await
+            .map_err(api_error)?;
         Ok(())
     }
 
-    pub async fn force_reboot_instance(&self, instance_id: &str) -> Result<()> {
-        // Force stop
+    async fn reboot_instance(&self, instance_id: &str) -> Result<()> {
         self.client
-            .stop_instances()
+            .reboot_instances()
             .instance_ids(instance_id)
-            .force(true)
             .send()
-            .await?;
-
-        // Wait for stopped state (5 minutes)
-        self.wait_until_stopped(instance_id, std::time::Duration::from_secs(300))
-            .await?;
+            .await
+            .map_err(api_error)?;
+        Ok(())
+    }
 
-        // Start
+    async fn set_stop_protection(&self, instance_id: &str, enabled: bool) -> Result<()> {
         self.client
-            .start_instances()
-            .instance_ids(instance_id)
+            .modify_instance_attribute()
+            .instance_id(instance_id)
+            .disable_api_stop(
+                aws_sdk_ec2::types::AttributeBooleanValue::builder()
+                    .value(enabled)
+                    .build(),
+            )
             .send()
-            .await?;
+            .await
+            .map_err(api_error)?;
+        Ok(())
+    }
 
+    async fn set_termination_protection(&self, instance_id: &str, enabled: bool) -> Result<()> {
+        self.client
+            .modify_instance_attribute()
+            .instance_id(instance_id)
+            .disable_api_termination(
+                aws_sdk_ec2::types::AttributeBooleanValue::builder()
+                    .value(enabled)
+                    .build(),
+            )
+            .send()
+            .await
+            .map_err(api_error)?;
         Ok(())
     }
 
-    pub async fn modify_instance_type(&self, instance_id: &str, new_type: &str) -> Result<()> {
+    async fn modify_instance_type(&self, instance_id: &str, new_type: &str) -> Result<()> {
         self.client
             .modify_instance_attribute()
             .instance_id(instance_id)
@@ -151,11 +473,12 @@ impl Ec2Client {
                     .build(),
             )
             .send()
-            .await?;
+            .await
+            .map_err(api_error)?;
         Ok(())
     }
 
-    pub async fn modify_credit_specification(
+    async fn modify_credit_specification(
         &self,
         instance_id: &str,
         credit_spec: &str,
@@ -169,48 +492,88 @@ impl Ec2Client {
                     .build(),
             )
             .send()
-            .await?;
+            .await
+            .map_err(api_error)?;
         Ok(())
     }
 
-    pub async fn wait_until_stopped(
+    async fn check_permissions(
         &self,
         instance_id: &str,
-        timeout: std::time::Duration,
-    ) -> Result<()> {
-        let start_time = std::time::Instant::now();
-        loop {
-            let resp = self
-                .client
-                .describe_instances()
-                .instance_ids(instance_id)
-                .send()
-                .await?;
-            let state = resp
-                .reservations
-                .unwrap_or_default()
-                .first()
-                .and_then(|r| r.instances.as_ref())
-                .and_then(|i| i.first())
-                .and_then(|i| i.state.as_ref())
-                .and_then(|s| s.name.as_ref())
-                .map(|n| n.as_str().to_string())
-                .unwrap_or_else(|| "unknown".to_string());
-
-            if state == "stopped" {
-                break;
-            }
-
-            if start_time.elapsed() > timeout {
-                return Err(anyhow::anyhow!("Timeout waiting for instance to stop"));
-            }
-
-            tokio::time::sleep(std::time::Duration::from_secs(2)).await;
-        }
-        Ok(())
+        instance_type: &str,
+        credit_spec: &str,
+    ) -> Vec<PermissionCheck> {
+        let stop = self
+            .client
+            .stop_instances()
+            .instance_ids(instance_id)
+            .dry_run(true)
+            .send()
+            .await;
+        let start = self
+            .client
+            .start_instances()
+            .instance_ids(instance_id)
+            .dry_run(true)
+            .send()
+            .await;
+        let reboot = self
+            .client
+            .reboot_instances()
+            .instance_ids(instance_id)
+            .dry_run(true)
+            .send()
+            .await;
+        let modify = self
+            .client
+            .modify_instance_attribute()
+            .instance_id(instance_id)
+            .instance_type(
+                aws_sdk_ec2::types::AttributeValue::builder()
+                    .value(instance_type)
+                    .build(),
+            )
+            .dry_run(true)
+            .send()
+            .await;
+        let credit = self
+            .client
+            .modify_instance_credit_specification()
+            .instance_credit_specifications(
+                aws_sdk_ec2::types::InstanceCreditSpecificationRequest::builder()
+                    .instance_id(instance_id)
+                    .cpu_credits(credit_spec)
+                    .build(),
+            )
+            .dry_run(true)
+            .send()
+            .await;
+
+        vec![
+            PermissionCheck {
+                operation: "StopInstances",
+                permission: dry_run_permission(stop),
+            },
+            PermissionCheck {
+                operation: "StartInstances",
+                permission: dry_run_permission(start),
+            },
+            PermissionCheck {
+                operation: "RebootInstances",
+                permission: dry_run_permission(reboot),
+            },
+            PermissionCheck {
+                operation: "ModifyInstanceAttribute",
+                permission: dry_run_permission(modify),
+            },
+            PermissionCheck {
+                operation: "ModifyInstanceCreditSpecification",
+                permission: dry_run_permission(credit),
+            },
+        ]
     }
 
-    pub async fn get_instance_types(&self) -> Result<Vec<InstanceTypeInfo>> {
+    async fn get_instance_types(&self) -> Result<Vec<InstanceTypeInfo>> {
         let mut types = Vec::new();
         let mut stream = self
             .client
@@ -219,7 +582,7 @@ impl Ec2Client {
             .send();
 
         while let Some(resp) = stream.next().await {
-            let resp = resp?;
+            let resp = resp.map_err(api_error)?;
             for t in resp.instance_types.unwrap_or_default() {
                 if let Some(name) = t.instance_type {
                     let name = name.as_str().to_string();
@@ -248,7 +611,7 @@ impl Ec2Client {
         Ok(types)
     }
 
-    pub async fn fetch_all_spot_prices(
+    async fn fetch_all_spot_prices(
         &self,
         az: &str,
     ) -> Result<std::collections::HashMap<String, f64>> {
@@ -265,7 +628,7 @@ impl Ec2Client {
             .send();
 
         while let Some(resp) = stream.next().await {
-            let resp = resp?;
+            let resp = resp.map_err(api_error)?;
             for entry in resp.spot_price_history.unwrap_or_default() {
                 if let (Some(t), Some(p)) = (entry.instance_type, entry.spot_price)
                     && let Ok(price) = p.parse::<f64>()
@@ -278,3 +641,353 @@ impl Ec2Client {
         Ok(prices)
     }
 }
+
+/// A state a waiter polls for.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum WaitFor {
+    Running,
+    Stopped,
+    Terminated,
+    StatusOk, // Both status checks passed
+}
+
+impl WaitFor {
+    pub fn label(self) -> &'static str {
+        match self {
+            WaitFor::Running => "running",
+            WaitFor::Stopped => "stopped",
+            WaitFor::Terminated => "terminated",
+            WaitFor::StatusOk => "status checks OK",
+        }
+    }
+}
+
+/// Polls start `interval` apart, double up to `max_interval`, and give up after `timeout`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    pub interval: Duration,
+    pub max_interval: Duration,
+    pub timeout: Duration,
+}
+
+/// Backoff for the state waiters and, since checks take minutes, for the status check waiter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Waiters {
+    pub state: Backoff,
+    pub status_checks: Backoff,
+}
+
+impl Waiters {
+    pub fn from_config(config: &AppConfig) -> Self {
+        let interval = Duration::from_secs(config.wait_interval_seconds.unwrap_or(2));
+        let max_interval = Duration::from_secs(config.wait_max_interval_seconds.unwrap_or(15));
+        Self {
+            state: Backoff {
+                interval,
+                max_interval,
+                timeout: Duration::from_secs(config.wait_timeout_seconds.unwrap_or(300)),
+            },
+            status_checks: Backoff {
+                interval,
+                max_interval,
+                timeout: Duration::from_secs(config.status_check_timeout_seconds.unwrap_or(900)),
+            },
+        }
+    }
+}
+
+impl Default for Waiters {
+    fn default() -> Self {
+        Self::from_config(&AppConfig::default())
+    }
+}
+
+/// Cheap clone handle over an `Ec2Backend` that adds read-only enforcement and auditing.
+#[derive(Clone)]
+pub struct Ec2Client {
+    backend: Arc<dyn Ec2Backend>,
+    read_only: bool,
+    auditor: Option<Arc<Auditor>>,
+    waiters: Waiters,
+}
+
+impl Ec2Client {
+    /// `endpoint_url` replaces the regional EC2 endpoint, e.g. with a local emulator.
+    /// Replayed sessions change nothing, so they are not audited.
+    pub async fn new(endpoint_url: Option<&str>, traffic: Option<&Traffic>) -> Result<Self> {
+        let region_provider = RegionProviderChain::default_provider().or_else("us-east-1");
+        #[allow(deprecated)]
+        let config = aws_config::from_env().region(region_provider).load().await;
+        let backend = SdkEc2::new(&config, endpoint_url, traffic)?;
+        Ok(Self {
+            auditor: (!traffic.is_some_and(Traffic::is_replay))
+                .then(|| Arc::new(Auditor::new(&config))),
+            ..Self::from_backend(Arc::new(backend))
+        })
+    }
+
+    /// Wraps an arbitrary backend without an audit log.
+    pub fn from_backend(backend: Arc<dyn Ec2Backend>) -> Self {
+        Self {
+            backend,
+            read_only: false,
+            auditor: None,
+            waiters: Waiters::default(),
+        }
+    }
+
+    #[cfg(test)]
+    pub fn new_mock() -> Self {
+        Self::from_backend(Arc::new(crate::fake::FakeFleet::new()))
+    }
+
+    /// Refuses every mutating call when `read_only` is set.
+    pub fn with_read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn with_waiters(mut self, waiters: Waiters) -> Self {
+        self.waiters = waiters;
+        self
+    }
+
+    fn ensure_writable(&self, operation: &str) -> Result<()> {
+        if self.read_only {
+            anyhow::bail!("{} refused: read-only mode", operation);
+        }
+        Ok(())
+    }
+
+    /// Awaits `call` and records it in the audit log, whether it succeeded or not.
+    async fn audited(
+        &self,
+        instance_id: &str,
+        action: &str,
+        parameters: &[(&str, String)],
+        call: impl std::future::Future<Output = Result<()>>,
+    ) -> Result<()> {
+        let result = call.await;
+        if let Some(auditor) = &self.auditor {
+            auditor
+                .record(instance_id, action, parameters, &result)
+                .await;
+        }
+        result
+    }
+
+    pub async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+        self.backend.list_instances().await
+    }
+
+    pub async fn describe_spot_request(&self, request_id: &str) -> Result<SpotRequestInfo> {
+        self.backend.describe_spot_request(request_id).await
+    }
+
+    pub async fn list_status_checks(
+        &self,
+    ) -> Result<std::collections::HashMap<String, StatusChecks>> {
+        self.backend.list_status_checks().await
+    }
+
+    pub async fn stop_instance(&self, instance_id: &str) -> Result<()> {
+        self.audited(instance_id, "stop", &[], async {
+            self.ensure_writable("StopInstances")?;
+            self.backend.stop_instance(instance_id, false).await
+        })
+        .await
+    }
+
+    pub async fn hibernate_instance(&self, instance_id: &str) -> Result<()> {
+        self.audited(instance_id, "hibernate", &[], async {
+            self.ensure_writable("StopInstances")?;
+            self.backend.hibernate_instance(instance_id).await
+        })
+        .await
+    }
+
+    pub async fn start_instance(&self, instance_id: &str) -> Result<()> {
+        self.audited(instance_id, "start", &[], async {
+            self.ensure_writable("StartInstances")?;
+            self.backend.start_instance(instance_id).await
+        })
+        .await
+    }
+
+    pub async fn reboot_instance(&self, instance_id: &str) -> Result<()> {
+        self.audited(instance_id, "reboot", &[], async {
+            self.ensure_writable("RebootInstances")?;
+            self.backend.reboot_instance(instance_id).await
+        })
+        .await
+    }
+
+    /// Cancelling ends the wait for the forced stop and skips the start, leaving the
+    /// instance stopped.
+    pub async fn force_reboot_instance(&self, instance_id: &str, cancel: &Cancel) -> Result<()> {
+        self.audited(instance_id, "force-reboot", &[], async {
+            self.ensure_writable("StopInstances")?;
+            // Force stop
+            self.backend.stop_instance(instance_id, true).await?;
+
+            self.wait_for(instance_id, WaitFor::Stopped, cancel).await?;
+
+            // Start
+            self.backend.start_instance(instance_id).await
+        })
+        .await
+    }
+
+    pub async fn get_protection(&self, instance_id: &str) -> Result<Protection> {
+        self.backend.get_protection(instance_id).await
+    }
+
+    pub async fn set_stop_protection(&self, instance_id: &str, enabled: bool) -> Result<()> {
+        self.audited(
+            instance_id,
+            "stop-protection",
+            &[("enabled", enabled.to_string())],
+            async {
+                self.ensure_writable("ModifyInstanceAttribute")?;
+                self.backend.set_stop_protection(instance_id, enabled).await
+            },
+        )
+        .await
+    }
+
+    pub async fn set_termination_protection(&self, instance_id: &str, enabled: bool) -> Result<()> {
+        self.audited(
+            instance_id,
+            "termination-protection",
+            &[("enabled", enabled.to_string())],
+            async {
+                self.ensure_writable("ModifyInstanceAttribute")?;
+                self.backend
+                    .set_termination_protection(instance_id, enabled)
+                    .await
+            },
+        )
+        .await
+    }
+
+    pub async fn modify_instance_type(&self, instance_id: &str, new_type: &str) -> Result<()> {
+        self.audited(
+            instance_id,
+            "resize",
+            &[("instance_type", new_type.to_string())],
+            async {
+                self.ensure_writable("ModifyInstanceAttribute")?;
+                self.backend
+                    .modify_instance_type(instance_id, new_type)
+                    .await
+            },
+        )
+        .await
+    }
+
+    pub async fn modify_credit_specification(
+        &self,
+        instance_id: &str,
+        credit_spec: &str,
+    ) -> Result<()> {
+        self.audited(
+            instance_id,
+            "credit-spec",
+            &[("cpu_credits", credit_spec.to_string())],
+            async {
+                self.ensure_writable("ModifyInstanceCreditSpecification")?;
+                self.backend
+                    .modify_credit_specification(instance_id, credit_spec)
+                    .await
+            },
+        )
+        .await
+    }
+
+    /// Sends every mutating request this tool uses against `instance_id` with `DryRun` set,
+    /// so nothing changes. Modifications reuse the current type and `credit_spec`.
+    pub async fn check_permissions(
+        &self,
+        instance_id: &str,
+        instance_type: &str,
+        credit_spec: &str,
+    ) -> Vec<PermissionCheck> {
+        self.backend
+            .check_permissions(instance_id, instance_type, credit_spec)
+            .await
+    }
+
+    /// Polls until `instance_id` reaches `target`, backing off between polls. Fails on
+    /// timeout, on cancellation, and when the instance terminates while waiting for anything
+    /// else.
+    pub async fn wait_for(
+        &self,
+        instance_id: &str,
+        target: WaitFor,
+        cancel: &Cancel,
+    ) -> Result<()> {
+        let backoff = if target == WaitFor::StatusOk {
+            self.waiters.status_checks
+        } else {
+            self.waiters.state
+        };
+        let deadline = tokio::time::Instant::now() + backoff.timeout;
+        let mut interval = backoff.interval;
+        loop {
+            let reached = if target == WaitFor::StatusOk {
+                let checks = self.backend.status_checks(instance_id).await?;
+                let impaired = checks.impaired();
+                if !impaired.is_empty() {
+                    anyhow::bail!(
+                        "{} is impaired ({} check failed)",
+                        instance_id,
+                        impaired.join(" and ")
+                    );
+                }
+                checks.ok()
+            } else {
+                let state = self.backend.instance_state(instance_id).await?;
+                if state == "terminated" && target != WaitFor::Terminated {
+                    anyhow::bail!(
+                        "{} terminated while waiting for it to be {}",
+                        instance_id,
+                        target.label()
+                    );
+                }
+                state == target.label()
+            };
+            if reached {
+                return Ok(());
+            }
+
+            if tokio::time::Instant::now() + interval > deadline {
+                anyhow::bail!(
+                    "Timed out after {}s waiting for {} to be {}",
+                    backoff.timeout.as_secs(),
+                    instance_id,
+                    target.label()
+                );
+            }
+            tokio::select! {
+                _ = tokio::time::sleep(interval) => {}
+                _ = cancel.cancelled() => anyhow::bail!(
+                    "Cancelled while waiting for {} to be {}",
+                    instance_id,
+                    target.label()
+                ),
+            }
+            interval = (interval * 2).min(backoff.max_interval);
+        }
+    }
+
+    pub async fn get_instance_types(&self) -> Result<Vec<InstanceTypeInfo>> {
+        self.backend.get_instance_types().await
+    }
+
+    pub async fn fetch_all_spot_prices(
+        &self,
+        az: &str,
+    ) -> Result<std::collections::HashMap<String, f64>> {
+        self.backend.fetch_all_spot_prices(az).await
+    }
+}
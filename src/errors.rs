@@ -0,0 +1,220 @@
+use aws_sdk_ec2::error::{DisplayErrorContext, ProvideErrorMetadata, SdkError};
+
+/// An AWS SDK error reduced to its error code and message, so it survives `anyhow`.
+#[derive(Debug)]
+pub struct ApiError {
+    pub code: Option<String>,
+    pub message: String,
+    pub detail: String, // Full debug output of the SDK error
+}
+
+impl std::fmt::Display for ApiError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.code {
+            Some(code) => write!(f, "{}: {}", code, self.message),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ApiError {}
+
+/// For `map_err` on SDK calls.
+pub fn api_error<E, R>(e: SdkError<E, R>) -> anyhow::Error
+where
+    E: ProvideErrorMetadata + std::error::Error + 'static,
+    R: std::fmt::Debug,
+{
+    anyhow::Error::new(ApiError {
+        code: e.code().map(|c| c.to_string()),
+        message: e
+            .message()
+            .map(|m| m.to_string())
+            .unwrap_or_else(|| DisplayErrorContext(&e).to_string()),
+        detail: format!("{:?}", e),
+    })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorKind {
+    IncorrectState,
+    InsufficientCapacity,
+    Unauthorized,
+    Throttled,
+    NotFound,
+    ExpiredCredentials,
+    MissingCredentials,
+    Other,
+}
+
+impl ErrorKind {
+    /// Classifies by EC2 error code. Credential providers fail before a request is sent, so
+    /// without a code expired credentials are recognised from the message.
+    pub fn classify(code: Option<&str>, message: &str) -> Self {
+        match code {
+            Some("IncorrectInstanceState") | Some("IncorrectState") => ErrorKind::IncorrectState,
+            Some("InsufficientInstanceCapacity") => ErrorKind::InsufficientCapacity,
+            Some("UnauthorizedOperation") | Some("AccessDenied") => ErrorKind::Unauthorized,
+            Some("RequestLimitExceeded") | Some("Throttling") => ErrorKind::Throttled,
+            Some("InvalidInstanceID.NotFound") | Some("InvalidInstanceID.Malformed") => {
+                ErrorKind::NotFound
+            }
+            Some("ExpiredToken") | Some("ExpiredTokenException") | Some("RequestExpired") => {
+                ErrorKind::ExpiredCredentials
+            }
+            Some(_) => ErrorKind::Other,
+            None => {
+                let message = message.to_lowercase();
+                if message.contains("expired")
+                    && (message.contains("token") || message.contains("credential"))
+                {
+                    ErrorKind::ExpiredCredentials
+                } else if message.contains("no credentials")
+                    || message.contains("failed to load credentials")
+                    || message.contains("credentials provider")
+                {
+                    ErrorKind::MissingCredentials
+                } else {
+                    ErrorKind::Other
+                }
+            }
+        }
+    }
+
+    /// Retrying won't help until the credentials are renewed.
+    pub fn is_credentials(self) -> bool {
+        matches!(
+            self,
+            ErrorKind::ExpiredCredentials | ErrorKind::MissingCredentials
+        )
+    }
+
+    fn summary(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::IncorrectState => {
+                Some("The instance is not in a state that allows this action.")
+            }
+            ErrorKind::InsufficientCapacity => {
+                Some("AWS has no spare capacity for this instance type in this Availability Zone.")
+            }
+            ErrorKind::Unauthorized => Some("Your AWS identity is not allowed to do this."),
+            ErrorKind::Throttled => Some("AWS is throttling requests from this account."),
+            ErrorKind::NotFound => Some("The instance does not exist in this region."),
+            ErrorKind::ExpiredCredentials => Some("Your AWS credentials have expired."),
+            ErrorKind::MissingCredentials => Some("No usable AWS credentials were found."),
+            ErrorKind::Other => None,
+        }
+    }
+
+    fn hint(self) -> Option<&'static str> {
+        match self {
+            ErrorKind::IncorrectState => {
+                Some("Wait for the current transition to finish, refresh, and try again.")
+            }
+            ErrorKind::InsufficientCapacity => {
+                Some("Try again in a few minutes, pick another instance type, or use another AZ.")
+            }
+            ErrorKind::Unauthorized => Some(
+                "Press D for a permission report, or ask an administrator for the missing permission.",
+            ),
+            ErrorKind::Throttled => {
+                Some("Wait a little before retrying, or raise refresh_interval_seconds.")
+            }
+            ErrorKind::NotFound => {
+                Some("It may have been terminated; the list will drop it on the next refresh.")
+            }
+            ErrorKind::ExpiredCredentials | ErrorKind::MissingCredentials => {
+                Some("Press l to log in again, or R to reload credentials refreshed elsewhere.")
+            }
+            ErrorKind::Other => None,
+        }
+    }
+}
+
+/// A failed operation described for people, with the raw error kept as `detail`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct FriendlyError {
+    pub context: String, // What was being done, e.g. "Stop failed"
+    pub kind: ErrorKind,
+    pub summary: String,
+    pub hint: Option<&'static str>,
+    pub detail: String,
+}
+
+impl FriendlyError {
+    pub fn new(context: &str, e: &anyhow::Error) -> Self {
+        let api = e.chain().find_map(|c| c.downcast_ref::<ApiError>());
+        let message = e.to_string();
+        let kind = ErrorKind::classify(api.and_then(|a| a.code.as_deref()), &message);
+        Self {
+            context: context.to_string(),
+            kind,
+            summary: kind.summary().map(|s| s.to_string()).unwrap_or(message),
+            hint: kind.hint(),
+            detail: api
+                .map(|a| a.detail.clone())
+                .unwrap_or_else(|| format!("{:?}", e)),
+        }
+    }
+}
+
+impl std::fmt::Display for FriendlyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.context, self.summary)?;
+        if let Some(hint) = self.hint {
+            write!(f, " {}", hint)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_codes() {
+        assert_eq!(
+            ErrorKind::classify(Some("IncorrectInstanceState"), ""),
+            ErrorKind::IncorrectState
+        );
+        assert_eq!(
+            ErrorKind::classify(Some("InvalidInstanceID.NotFound"), ""),
+            ErrorKind::NotFound
+        );
+        assert_eq!(
+            ErrorKind::classify(Some("SomethingElse"), "token expired"),
+            ErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_classify_expired_credentials_without_code() {
+        assert_eq!(
+            ErrorKind::classify(None, "the SSO session token has Expired"),
+            ErrorKind::ExpiredCredentials
+        );
+        assert_eq!(
+            ErrorKind::classify(None, "dispatch failure"),
+            ErrorKind::Other
+        );
+    }
+
+    #[test]
+    fn test_friendly_error_keeps_detail() {
+        let e = anyhow::Error::new(ApiError {
+            code: Some("InsufficientInstanceCapacity".to_string()),
+            message: "We currently do not have sufficient capacity".to_string(),
+            detail: "ServiceError { .. }".to_string(),
+        });
+        let friendly = FriendlyError::new("Start failed", &e);
+
+        assert_eq!(friendly.kind, ErrorKind::InsufficientCapacity);
+        assert_eq!(friendly.detail, "ServiceError { .. }");
+        assert!(
+            friendly
+                .to_string()
+                .starts_with("Start failed: AWS has no spare capacity")
+        );
+    }
+}
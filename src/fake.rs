@@ -0,0 +1,630 @@
+use crate::ec2::{
+    Ec2Backend, InstanceInfo, InstanceTypeInfo, Permission, PermissionCheck, Protection,
+    SpotRequestInfo, StatusChecks,
+};
+use crate::errors::ApiError;
+use crate::pricing::PricingBackend;
+use anyhow::Result;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A bare on-demand instance to seed a fleet with.
+pub fn instance(id: &str, instance_type: &str, state: &str) -> InstanceInfo {
+    InstanceInfo {
+        id: id.to_string(),
+        name: None,
+        instance_type: instance_type.to_string(),
+        state: state.to_string(),
+        architecture: Some("x86_64".to_string()),
+        availability_zone: Some("us-east-1a".to_string()),
+        public_ip: None,
+        launch_time: None,
+        instance_lifecycle: None,
+        spot_instance_request_id: None,
+        tags: HashMap::new(),
+        hibernation_configured: false,
+        state_reason: None,
+    }
+}
+
+struct FakeInstance {
+    info: InstanceInfo,
+    protection: Protection,
+    transition: Option<(Instant, &'static str)>, // State to enter once the deadline passes
+    checks_ok_at: Option<Instant>,               // Status checks initialize until then
+    impaired: bool,                              // Instance check fails once initialized
+}
+
+impl FakeInstance {
+    fn status_checks(&self) -> StatusChecks {
+        let status = if self.info.state != "running" {
+            "not-applicable"
+        } else if self.checks_ok_at.is_some_and(|at| Instant::now() < at) {
+            "initializing"
+        } else {
+            "ok"
+        };
+        StatusChecks {
+            system: status.to_string(),
+            instance: if status == "ok" && self.impaired {
+                "impaired"
+            } else {
+                status
+            }
+            .to_string(),
+            ebs: status.to_string(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct Fleet {
+    instances: Vec<FakeInstance>,
+    start_delay: Duration,
+    stop_delay: Duration,
+    check_delay: Duration, // Status checks initializing after a boot
+    latency: Duration,     // Added to every call
+    instance_types: Vec<InstanceTypeInfo>,
+    spot_prices: HashMap<String, f64>,
+    spot_requests: HashMap<String, SpotRequestInfo>,
+    failures: HashMap<&'static str, (String, String)>, // Operation -> (code, message)
+    calls: Vec<String>,
+}
+
+impl Fleet {
+    /// Applies every transition whose deadline has passed.
+    fn settle(&mut self) {
+        let now = Instant::now();
+        for instance in &mut self.instances {
+            if let Some((at, state)) = instance.transition
+                && at <= now
+            {
+                instance.info.state = state.to_string();
+                instance.transition = None;
+            }
+        }
+    }
+
+    /// Records the call, then returns the injected error for `operation`, if any.
+    fn call(&mut self, operation: &'static str, instance_id: &str) -> Result<()> {
+        self.calls.push(format!("{} {}", operation, instance_id));
+        self.settle();
+        match self.failures.get(operation) {
+            Some((code, message)) => Err(error(code, message)),
+            None => Ok(()),
+        }
+    }
+
+    fn get(&mut self, instance_id: &str) -> Result<&mut FakeInstance> {
+        self.instances
+            .iter_mut()
+            .find(|i| i.info.id == instance_id)
+            .ok_or_else(|| {
+                error(
+                    "InvalidInstanceID.NotFound",
+                    &format!("The instance ID '{}' does not exist", instance_id),
+                )
+            })
+    }
+
+    /// Moves the instance into `through` now and into `to` after `delay`.
+    fn transition(
+        &mut self,
+        instance_id: &str,
+        through: &'static str,
+        to: &'static str,
+        delay: Duration,
+    ) -> Result<()> {
+        let instance = self.get(instance_id)?;
+        if delay.is_zero() {
+            instance.info.state = to.to_string();
+        } else {
+            instance.info.state = through.to_string();
+            instance.transition = Some((Instant::now() + delay, to));
+        }
+        Ok(())
+    }
+}
+
+fn error(code: &str, message: &str) -> anyhow::Error {
+    anyhow::Error::new(ApiError {
+        code: Some(code.to_string()),
+        message: message.to_string(),
+        detail: format!("fake {}: {}", code, message),
+    })
+}
+
+fn incorrect_state(instance: &FakeInstance, operation: &str) -> anyhow::Error {
+    error(
+        "IncorrectInstanceState",
+        &format!(
+            "The instance '{}' is in state '{}' and cannot be used for {}",
+            instance.info.id, instance.info.state, operation
+        ),
+    )
+}
+
+/// An in-memory EC2 backend. Instances pass through "pending" and "stopping" for
+/// configurable delays on the tokio clock, so tests with paused time run instantly.
+#[derive(Default)]
+pub struct FakeFleet {
+    fleet: Mutex<Fleet>,
+}
+
+impl FakeFleet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Instances seeded as "pending" or "stopping" finish the transition after the
+    /// configured delay, so set delays first.
+    pub fn with_instance(self, info: InstanceInfo) -> Self {
+        {
+            let mut fleet = self.lock();
+            let transition = match info.state.as_str() {
+                "pending" => Some((Instant::now() + fleet.start_delay, "running")),
+                "stopping" => Some((Instant::now() + fleet.stop_delay, "stopped")),
+                _ => None,
+            };
+            fleet.instances.push(FakeInstance {
+                info,
+                protection: Protection::default(),
+                transition,
+                checks_ok_at: None,
+                impaired: false,
+            });
+        }
+        self
+    }
+
+    pub fn with_protection(self, instance_id: &str, protection: Protection) -> Self {
+        if let Ok(instance) = self.lock().get(instance_id) {
+            instance.protection = protection;
+        }
+        self
+    }
+
+    /// Makes the instance status check of `instance_id` fail whenever it runs.
+    pub fn with_impaired(self, instance_id: &str) -> Self {
+        if let Ok(instance) = self.lock().get(instance_id) {
+            instance.impaired = true;
+        }
+        self
+    }
+
+    /// How long instances spend in "pending" and "stopping". Both are instant by default.
+    pub fn with_delays(self, start: Duration, stop: Duration) -> Self {
+        {
+            let mut fleet = self.lock();
+            fleet.start_delay = start;
+            fleet.stop_delay = stop;
+        }
+        self
+    }
+
+    /// How long status checks stay "initializing" once an instance is running or rebooted.
+    pub fn with_check_delay(self, delay: Duration) -> Self {
+        self.lock().check_delay = delay;
+        self
+    }
+
+    /// How long every call takes to answer. Instant by default.
+    pub fn with_latency(self, latency: Duration) -> Self {
+        self.lock().latency = latency;
+        self
+    }
+
+    /// Without a catalog, only the types of the seeded instances are offered.
+    pub fn with_instance_types(self, instance_types: Vec<InstanceTypeInfo>) -> Self {
+        self.lock().instance_types = instance_types;
+        self
+    }
+
+    /// Spot prices by instance type, the same in every AZ.
+    pub fn with_spot_prices(self, spot_prices: HashMap<String, f64>) -> Self {
+        self.lock().spot_prices = spot_prices;
+        self
+    }
+
+    pub fn with_spot_request(self, request: SpotRequestInfo) -> Self {
+        self.lock()
+            .spot_requests
+            .insert(request.id.clone(), request);
+        self
+    }
+
+    /// Makes every later call to `operation` (e.g. "StopInstances") fail with `code`.
+    #[cfg(test)]
+    pub fn fail(&self, operation: &'static str, code: &str, message: &str) {
+        self.lock()
+            .failures
+            .insert(operation, (code.to_string(), message.to_string()));
+    }
+
+    #[cfg(test)]
+    pub fn recover(&self, operation: &'static str) {
+        self.lock().failures.remove(operation);
+    }
+
+    /// Current view of one instance, with elapsed transitions applied.
+    #[cfg(test)]
+    pub fn instance(&self, instance_id: &str) -> Option<InstanceInfo> {
+        let mut fleet = self.lock();
+        fleet.settle();
+        fleet
+            .instances
+            .iter()
+            .find(|i| i.info.id == instance_id)
+            .map(|i| i.info.clone())
+    }
+
+    /// Every call so far as "<Operation> <instance id>", oldest first.
+    #[cfg(test)]
+    pub fn calls(&self) -> Vec<String> {
+        self.lock().calls.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Fleet> {
+        self.fleet.lock().unwrap()
+    }
+
+    async fn pause(&self) {
+        let latency = self.lock().latency;
+        if !latency.is_zero() {
+            tokio::time::sleep(latency).await;
+        }
+    }
+}
+
+#[async_trait]
+impl Ec2Backend for FakeFleet {
+    async fn list_instances(&self) -> Result<Vec<InstanceInfo>> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeInstances", "*")?;
+        Ok(fleet.instances.iter().map(|i| i.info.clone()).collect())
+    }
+
+    async fn instance_state(&self, instance_id: &str) -> Result<String> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeInstances", instance_id)?;
+        Ok(fleet.get(instance_id)?.info.state.clone())
+    }
+
+    async fn status_checks(&self, instance_id: &str) -> Result<StatusChecks> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeInstanceStatus", instance_id)?;
+        Ok(fleet.get(instance_id)?.status_checks())
+    }
+
+    async fn list_status_checks(&self) -> Result<HashMap<String, StatusChecks>> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeInstanceStatus", "*")?;
+        Ok(fleet
+            .instances
+            .iter()
+            .filter(|i| i.info.state == "running")
+            .map(|i| (i.info.id.clone(), i.status_checks()))
+            .collect())
+    }
+
+    async fn describe_spot_request(&self, request_id: &str) -> Result<SpotRequestInfo> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeSpotInstanceRequests", request_id)?;
+        fleet.spot_requests.get(request_id).cloned().ok_or_else(|| {
+            error(
+                "InvalidSpotInstanceRequestID.NotFound",
+                &format!(
+                    "The spot instance request ID '{}' does not exist",
+                    request_id
+                ),
+            )
+        })
+    }
+
+    async fn stop_instance(&self, instance_id: &str, force: bool) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("StopInstances", instance_id)?;
+        let delay = fleet.stop_delay;
+        let instance = fleet.get(instance_id)?;
+        if instance.protection.stop {
+            return Err(error(
+                "OperationNotPermitted",
+                "The instance may not be stopped. Modify its 'disableApiStop' attribute and try again.",
+            ));
+        }
+        match instance.info.state.as_str() {
+            "running" | "pending" => {}
+            // A forced stop takes over an instance that is already stopping
+            "stopping" if force => {}
+            "stopped" | "stopping" => return Ok(()),
+            _ => return Err(incorrect_state(instance, "StopInstances")),
+        }
+        instance.info.state_reason = Some("Client.UserInitiatedShutdown".to_string());
+        fleet.transition(instance_id, "stopping", "stopped", delay)
+    }
+
+    async fn hibernate_instance(&self, instance_id: &str) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("StopInstances", instance_id)?;
+        let delay = fleet.stop_delay;
+        let instance = fleet.get(instance_id)?;
+        if !instance.info.hibernation_configured {
+            return Err(error(
+                "UnsupportedHibernationConfiguration",
+                "The instance is not enabled for hibernation",
+            ));
+        }
+        if instance.info.state != "running" {
+            return Err(incorrect_state(instance, "StopInstances"));
+        }
+        instance.info.state_reason = Some("Client.UserInitiatedHibernate".to_string());
+        fleet.transition(instance_id, "stopping", "stopped", delay)
+    }
+
+    async fn start_instance(&self, instance_id: &str) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("StartInstances", instance_id)?;
+        let delay = fleet.start_delay;
+        let instance = fleet.get(instance_id)?;
+        match instance.info.state.as_str() {
+            "stopped" => {}
+            "running" | "pending" => return Ok(()),
+            _ => return Err(incorrect_state(instance, "StartInstances")),
+        }
+        instance.info.state_reason = None;
+        let check_delay = fleet.check_delay;
+        fleet.get(instance_id)?.checks_ok_at = Some(Instant::now() + delay + check_delay);
+        fleet.transition(instance_id, "pending", "running", delay)
+    }
+
+    async fn reboot_instance(&self, instance_id: &str) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("RebootInstances", instance_id)?;
+        let check_delay = fleet.check_delay;
+        let instance = fleet.get(instance_id)?;
+        if instance.info.state != "running" {
+            return Err(incorrect_state(instance, "RebootInstances"));
+        }
+        instance.checks_ok_at = Some(Instant::now() + check_delay);
+        Ok(())
+    }
+
+    async fn get_protection(&self, instance_id: &str) -> Result<Protection> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeInstanceAttribute", instance_id)?;
+        Ok(fleet.get(instance_id)?.protection)
+    }
+
+    async fn set_stop_protection(&self, instance_id: &str, enabled: bool) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("ModifyInstanceAttribute", instance_id)?;
+        fleet.get(instance_id)?.protection.stop = enabled;
+        Ok(())
+    }
+
+    async fn set_termination_protection(&self, instance_id: &str, enabled: bool) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("ModifyInstanceAttribute", instance_id)?;
+        fleet.get(instance_id)?.protection.termination = enabled;
+        Ok(())
+    }
+
+    async fn modify_instance_type(&self, instance_id: &str, new_type: &str) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("ModifyInstanceAttribute", instance_id)?;
+        let instance = fleet.get(instance_id)?;
+        if instance.info.state != "stopped" {
+            return Err(incorrect_state(instance, "ModifyInstanceAttribute"));
+        }
+        instance.info.instance_type = new_type.to_string();
+        Ok(())
+    }
+
+    async fn modify_credit_specification(
+        &self,
+        instance_id: &str,
+        _credit_spec: &str,
+    ) -> Result<()> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("ModifyInstanceCreditSpecification", instance_id)?;
+        fleet.get(instance_id)?;
+        Ok(())
+    }
+
+    async fn check_permissions(
+        &self,
+        instance_id: &str,
+        _instance_type: &str,
+        _credit_spec: &str,
+    ) -> Vec<PermissionCheck> {
+        self.pause().await;
+        let fleet = self.lock();
+        [
+            "StopInstances",
+            "StartInstances",
+            "RebootInstances",
+            "ModifyInstanceAttribute",
+            "ModifyInstanceCreditSpecification",
+        ]
+        .into_iter()
+        .map(|operation| PermissionCheck {
+            operation,
+            permission: match fleet.failures.get(operation) {
+                Some((code, _)) if code == "UnauthorizedOperation" => Permission::Denied,
+                Some((code, _)) => Permission::Unknown(code.clone()),
+                None if fleet.instances.iter().any(|i| i.info.id == instance_id) => {
+                    Permission::Allowed
+                }
+                None => Permission::Unknown("InvalidInstanceID.NotFound".to_string()),
+            },
+        })
+        .collect()
+    }
+
+    async fn get_instance_types(&self) -> Result<Vec<InstanceTypeInfo>> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeInstanceTypes", "*")?;
+        if !fleet.instance_types.is_empty() {
+            return Ok(fleet.instance_types.clone());
+        }
+        let mut types: Vec<String> = fleet
+            .instances
+            .iter()
+            .map(|i| i.info.instance_type.clone())
+            .collect();
+        types.sort();
+        types.dedup();
+        Ok(types
+            .into_iter()
+            .map(|name| InstanceTypeInfo {
+                name,
+                architectures: vec!["x86_64".to_string()],
+                vcpu: Some(2),
+                memory_mib: Some(4096),
+            })
+            .collect())
+    }
+
+    async fn fetch_all_spot_prices(&self, az: &str) -> Result<HashMap<String, f64>> {
+        self.pause().await;
+        let mut fleet = self.lock();
+        fleet.call("DescribeSpotPriceHistory", az)?;
+        Ok(fleet.spot_prices.clone())
+    }
+}
+
+/// Fixed on-demand prices.
+pub struct FakePricing {
+    pub prices: HashMap<String, f64>,
+}
+
+#[async_trait]
+impl PricingBackend for FakePricing {
+    async fn fetch_all_on_demand_prices(&self) -> Result<HashMap<String, f64>> {
+        Ok(self.prices.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(start_paused = true)]
+    async fn test_stop_and_start_take_the_configured_delays() {
+        let fleet = FakeFleet::new()
+            .with_instance(instance("i-1", "t3.micro", "running"))
+            .with_delays(Duration::from_secs(5), Duration::from_secs(10));
+
+        fleet.stop_instance("i-1", false).await.unwrap();
+        assert_eq!(fleet.instance("i-1").unwrap().state, "stopping");
+        tokio::time::sleep(Duration::from_secs(10)).await;
+        assert_eq!(fleet.instance("i-1").unwrap().state, "stopped");
+
+        fleet.start_instance("i-1").await.unwrap();
+        assert_eq!(fleet.instance("i-1").unwrap().state, "pending");
+        tokio::time::sleep(Duration::from_secs(5)).await;
+        assert_eq!(fleet.instance("i-1").unwrap().state, "running");
+    }
+
+    #[tokio::test]
+    async fn test_injected_errors_keep_their_code() {
+        let fleet = FakeFleet::new().with_instance(instance("i-1", "t3.micro", "running"));
+        fleet.fail("StopInstances", "UnauthorizedOperation", "not allowed");
+
+        let err = fleet.stop_instance("i-1", false).await.unwrap_err();
+        let api = err.downcast_ref::<ApiError>().unwrap();
+        assert_eq!(api.code.as_deref(), Some("UnauthorizedOperation"));
+        assert_eq!(fleet.instance("i-1").unwrap().state, "running");
+
+        fleet.recover("StopInstances");
+        fleet.stop_instance("i-1", false).await.unwrap();
+        assert_eq!(fleet.instance("i-1").unwrap().state, "stopped");
+    }
+
+    #[tokio::test]
+    async fn test_stop_protection_refuses_stop() {
+        let fleet = FakeFleet::new()
+            .with_instance(instance("i-1", "t3.micro", "running"))
+            .with_protection(
+                "i-1",
+                Protection {
+                    stop: true,
+                    termination: false,
+                },
+            );
+
+        assert!(fleet.stop_instance("i-1", true).await.is_err());
+        assert_eq!(fleet.instance("i-1").unwrap().state, "running");
+        assert!(fleet.get_protection("i-1").await.unwrap().stop);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_waiters_back_off_and_time_out() {
+        use crate::ec2::{Backoff, Ec2Client, WaitFor, Waiters};
+        use crate::operations::Cancel;
+        use std::sync::Arc;
+
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance("i-1", "t3.micro", "stopped"))
+                .with_delays(Duration::from_secs(30), Duration::from_secs(0))
+                .with_check_delay(Duration::from_secs(60)),
+        );
+        let backoff = Backoff {
+            interval: Duration::from_secs(1),
+            max_interval: Duration::from_secs(8),
+            timeout: Duration::from_secs(20),
+        };
+        let client = Ec2Client::from_backend(fleet.clone()).with_waiters(Waiters {
+            state: backoff,
+            status_checks: Backoff {
+                timeout: Duration::from_secs(120),
+                ..backoff
+            },
+        });
+        let cancel = Cancel::default();
+
+        fleet.start_instance("i-1").await.unwrap();
+        let err = client
+            .wait_for("i-1", WaitFor::Running, &cancel)
+            .await
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Timed out after 20s"));
+        // Polled after 0, 1, 3, 7 and 15 seconds
+        let polls = fleet
+            .calls()
+            .iter()
+            .filter(|c| c.starts_with("DescribeInstances"))
+            .count();
+        assert_eq!(polls, 5);
+
+        client
+            .wait_for("i-1", WaitFor::Running, &cancel)
+            .await
+            .unwrap();
+        assert!(!fleet.status_checks("i-1").await.unwrap().ok());
+        client
+            .wait_for("i-1", WaitFor::StatusOk, &cancel)
+            .await
+            .unwrap();
+        assert!(fleet.status_checks("i-1").await.unwrap().ok());
+    }
+}
@@ -0,0 +1,101 @@
+use crate::config::AppConfig;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct IdleThresholds {
+    pub cpu_percent: f64,
+    /// Combined NetworkIn + NetworkOut per hour.
+    pub network_bytes_per_hour: f64,
+    pub hours: u32,
+}
+
+impl IdleThresholds {
+    pub fn from_config(config: &AppConfig) -> Self {
+        Self {
+            cpu_percent: config.idle_cpu_threshold_percent.unwrap_or(5.0),
+            network_bytes_per_hour: config
+                .idle_network_threshold_bytes
+                .unwrap_or(5.0 * 1024.0 * 1024.0),
+            hours: config.idle_hours.unwrap_or(72),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IdleCandidate {
+    pub instance_id: String,
+    pub name: Option<String>,
+    pub instance_type: String,
+    pub max_cpu: f64,
+    pub max_network_bytes: f64,
+    pub hours: u32,
+    pub marked: bool,
+}
+
+/// Checks hourly CPU maxima and network totals against `thresholds`. Returns the observed
+/// (max CPU, max network per hour) if every hour stayed below both thresholds.
+///
+/// At least 90% of the window must have datapoints, so recently launched or just started
+/// instances are not flagged on the strength of a few quiet hours.
+pub fn evaluate(
+    cpu_max: &[f64],
+    network_per_hour: &[f64],
+    thresholds: &IdleThresholds,
+) -> Option<(f64, f64)> {
+    let required = (thresholds.hours as usize * 9).div_ceil(10);
+    if cpu_max.len() < required || network_per_hour.len() < required {
+        return None;
+    }
+
+    let max_cpu = cpu_max.iter().copied().fold(0.0, f64::max);
+    let max_network = network_per_hour.iter().copied().fold(0.0, f64::max);
+    if max_cpu < thresholds.cpu_percent && max_network < thresholds.network_bytes_per_hour {
+        Some((max_cpu, max_network))
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const THRESHOLDS: IdleThresholds = IdleThresholds {
+        cpu_percent: 5.0,
+        network_bytes_per_hour: 1_000_000.0,
+        hours: 10,
+    };
+
+    #[test]
+    fn test_quiet_instance_is_idle() {
+        let cpu = vec![1.0; 10];
+        let net = vec![50_000.0; 10];
+
+        assert_eq!(evaluate(&cpu, &net, &THRESHOLDS), Some((1.0, 50_000.0)));
+    }
+
+    #[test]
+    fn test_single_busy_hour_is_not_idle() {
+        let mut cpu = vec![1.0; 10];
+        cpu[3] = 40.0;
+        let net = vec![50_000.0; 10];
+
+        assert_eq!(evaluate(&cpu, &net, &THRESHOLDS), None);
+    }
+
+    #[test]
+    fn test_network_traffic_is_not_idle() {
+        let cpu = vec![1.0; 10];
+        let mut net = vec![50_000.0; 10];
+        net[9] = 20_000_000.0;
+
+        assert_eq!(evaluate(&cpu, &net, &THRESHOLDS), None);
+    }
+
+    #[test]
+    fn test_short_history_is_not_idle() {
+        let cpu = vec![1.0; 5];
+        let net = vec![0.0; 5];
+
+        assert_eq!(evaluate(&cpu, &net, &THRESHOLDS), None);
+    }
+}
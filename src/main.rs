@@ -1,18 +1,42 @@
+mod actions;
 mod app;
+mod audit;
+mod cli;
+mod cloudwatch;
 mod config;
+mod daemon;
+mod demo;
 mod ec2;
+mod errors;
+mod fake;
+mod idle;
+mod operations;
+mod policy;
 mod pricing;
+mod recording;
+mod rightsizing;
+mod schedule;
+mod spot_advisor;
 mod ui;
 
 use anyhow::Result;
-use app::{App, AppEvent, AppState};
+use app::{App, AppEvent, AppState, PendingAction};
+use clap::Parser;
+use config::AppConfig;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyModifiers},
+    event::{
+        DisableMouseCapture, EnableMouseCapture, Event, EventStream, KeyCode, KeyEvent,
+        KeyEventKind, KeyModifiers,
+    },
     execute,
     terminal::{EnterAlternateScreen, LeaveAlternateScreen, disable_raw_mode, enable_raw_mode},
 };
+use ec2::{InstanceInfo, WaitFor};
+use errors::FriendlyError;
+use futures::StreamExt;
 #[cfg(target_env = "musl")]
 use mimalloc::MiMalloc;
+use operations::Cancel;
 use ratatui::{Terminal, backend::CrosstermBackend};
 use std::{io, time::Duration};
 
@@ -23,6 +47,7 @@ static GLOBAL: MiMalloc = MiMalloc;
 // Validated that cleanup is necessary on error
 struct Tui {
     terminal: Terminal<CrosstermBackend<std::io::Stdout>>,
+    events: EventStream,
 }
 
 impl Tui {
@@ -32,7 +57,36 @@ impl Tui {
         execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
         let backend = CrosstermBackend::new(stdout);
         let terminal = Terminal::new(backend)?;
-        Ok(Self { terminal })
+        Ok(Self {
+            terminal,
+            events: EventStream::new(),
+        })
+    }
+
+    /// Hands the terminal back to the shell, e.g. for an interactive login.
+    fn suspend(&mut self) -> Result<()> {
+        // Dropping the stream stops its reader thread, which would otherwise eat the input
+        // meant for the child process
+        self.events = EventStream::new();
+        disable_raw_mode()?;
+        execute!(
+            self.terminal.backend_mut(),
+            LeaveAlternateScreen,
+            DisableMouseCapture
+        )?;
+        self.terminal.show_cursor()?;
+        Ok(())
+    }
+
+    fn resume(&mut self) -> Result<()> {
+        enable_raw_mode()?;
+        execute!(
+            self.terminal.backend_mut(),
+            EnterAlternateScreen,
+            EnableMouseCapture
+        )?;
+        self.terminal.clear()?;
+        Ok(())
     }
 }
 
@@ -50,708 +104,1937 @@ impl Drop for Tui {
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let cli = cli::Cli::parse();
+    if let Some(cli::Command::Daemon { interval_seconds }) = cli.command {
+        let config = AppConfig::load().await?;
+        if cli.read_only || config.read_only.unwrap_or(false) {
+            anyhow::bail!("The schedule daemon cannot run in read-only mode");
+        }
+        let endpoints = config.endpoints(&cli.endpoints());
+        return daemon::run(
+            config,
+            endpoints,
+            cli.traffic()?,
+            Duration::from_secs(interval_seconds),
+        )
+        .await;
+    }
+
+    // Open the session file before the terminal is taken over, so errors stay readable
+    let traffic = cli.traffic()?;
+
     // Setup terminal with RAII guard
     let mut tui = Tui::new()?;
 
     // Create App
     // If this fails, tui dropped -> cleanup happens
-    let mut app = App::new().await?;
+    let mut app = if cli.demo {
+        App::new_demo(cli.read_only).await?
+    } else {
+        App::new(cli.read_only, &cli.endpoints(), traffic).await?
+    };
 
-    // Main loop
-    let mut last_tick = std::time::Instant::now();
+    // Main loop: sleeps until a key, a background result or the refresh timer wakes it,
+    // and only redraws after something changed
+    let period = Duration::from_secs(app.config.refresh_interval_seconds.unwrap_or(5));
+    let mut refresh = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+    refresh.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+    let mut spinner = tokio::time::interval(Duration::from_millis(100));
+    let mut dirty = true;
     loop {
-        tui.terminal.draw(|f| ui::draw(f, &mut app))?;
-
-        // Timer check
-        if last_tick.elapsed()
-            >= Duration::from_secs(app.config.refresh_interval_seconds.unwrap_or(5))
-        {
-            let client = app.ec2_client.clone();
-            let tx = app.event_tx.clone();
-            tokio::spawn(async move {
-                match client.list_instances().await {
-                    Ok(instances) => {
-                        let _ = tx.send(AppEvent::InstancesFetched(instances)).await;
+        if dirty {
+            tui.terminal.draw(|f| ui::draw(f, &mut app))?;
+            dirty = false;
+        }
+
+        tokio::select! {
+            Some(event) = app.event_rx.recv() => {
+                handle_event(&mut app, event);
+                // Apply the rest of a burst before drawing again
+                while let Ok(event) = app.event_rx.try_recv() {
+                    handle_event(&mut app, event);
+                }
+                dirty = true;
+            }
+            _ = refresh.tick() => {
+                // Paused while the credentials are unusable
+                if app.credentials_problem.is_none() {
+                    spawn_refresh(&app);
+                    if app.show_metrics {
+                        app.fetch_metrics();
                     }
-                    Err(e) => {
-                        let _ = tx.send(AppEvent::Error(e.to_string())).await;
+                }
+            }
+            _ = spinner.tick(), if !app.loading.is_empty() => {
+                app.spinner = app.spinner.wrapping_add(1);
+                dirty = true;
+            }
+            event = tui.events.next() => match event {
+                Some(Ok(Event::Key(key))) => {
+                    if key.kind == KeyEventKind::Press {
+                        handle_key(&mut app, &mut tui, key).await?;
+                        dirty = true;
                     }
                 }
-            });
-            last_tick = std::time::Instant::now();
+                Some(Ok(Event::Resize(_, _))) => dirty = true,
+                Some(Ok(_)) => {}
+                Some(Err(e)) => return Err(e.into()),
+                None => break,
+            },
         }
 
-        // Check for async events
-        // Check for async events
-        if let Ok(event) = app.event_rx.try_recv() {
-            match event {
-                AppEvent::InstancesFetched(instances) => {
-                    app.instances = instances;
-                    app.update_filter();
-                    app.last_refreshed = Some(chrono::Local::now().format("%H:%M:%S").to_string());
-                }
+        if app.should_quit {
+            break;
+        }
+    }
 
-                AppEvent::BulkOnDemandFetched(prices) => {
-                    for (t, p) in prices {
-                        let entry = app.type_prices.entry(t.clone()).or_insert(app::Prices {
-                            on_demand: None,
-                            spot: None,
-                        });
-                        entry.on_demand = Some(p);
+    // Restore terminal handled by Tui Drop
+
+    // Save config on exit; the demo runs on defaults and must not overwrite the real file
+    if !cli.demo {
+        app.config.filter = Some(app.filter.clone());
+        app.config.save().await?;
+    }
+
+    Ok(())
+}
+
+/// Applies a key press to the current screen.
+async fn handle_key(app: &mut App, tui: &mut Tui, key: KeyEvent) -> Result<()> {
+    if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        app.should_quit = true;
+    }
+
+    let current_state = app.state.clone();
+    match current_state {
+        AppState::List => {
+            match key.code {
+                KeyCode::Char('q') => app.should_quit = true,
+                KeyCode::Down => app.next(),
+                KeyCode::Up => app.previous(),
+                KeyCode::PageDown => {
+                    let i = match app.list_state.selected() {
+                        Some(i) => (i + 20).min(app.filtered_instances.len().saturating_sub(1)),
+                        None => 0,
+                    };
+                    app.list_state.select(Some(i));
+                }
+                KeyCode::PageUp => {
+                    let i = match app.list_state.selected() {
+                        Some(i) => i.saturating_sub(20),
+                        None => 0,
+                    };
+                    app.list_state.select(Some(i));
+                }
+                KeyCode::Home => app.list_state.select(Some(0)),
+                KeyCode::End => {
+                    let i = app.filtered_instances.len().saturating_sub(1);
+                    app.list_state.select(Some(i));
+                }
+                KeyCode::Char('f') => app.state = AppState::FilterInput,
+                KeyCode::Char('m') => {
+                    app.show_metrics = !app.show_metrics;
+                    if app.show_metrics {
+                        app.fetch_metrics();
+                    }
+                }
+                KeyCode::Char('w') => {
+                    if app.show_metrics {
+                        app.metrics_window = app.metrics_window.next();
+                        app.metrics = None;
+                        app.fetch_metrics();
                     }
-                    if let AppState::SelectingType {
-                        options,
-                        selected_index,
-                        default_mode_active,
-                        ..
-                    } = &mut app.state
+                }
+                KeyCode::Char('c') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx)
                     {
-                        // Only re-sort by price if the user has engaged with the input (default mode is NOT active).
-                        // If default_mode_active is true, we keep the list sorted alphabetically to avoid confusing the user
-                        // with a re-order while they are viewing the default suggestion.
-                        if !*default_mode_active {
-                            let type_prices = &app.type_prices;
-                            options.sort_by(|a, b| {
-                                let price_a = type_prices
-                                    .get(a)
-                                    .and_then(|p| p.on_demand)
-                                    .unwrap_or(f64::MAX);
-                                let price_b = type_prices
-                                    .get(b)
-                                    .and_then(|p| p.on_demand)
-                                    .unwrap_or(f64::MAX);
-                                price_a
-                                    .partial_cmp(&price_b)
-                                    .unwrap_or(std::cmp::Ordering::Equal)
-                                    .then_with(|| a.cmp(b))
+                        // Resizing goes through stop/start, which only persistent spot requests allow
+                        if instance.is_spot() && !app.spot_requests.contains_key(&instance.id) {
+                            app.fetch_spot_request(instance);
+                        }
+
+                        let mut initial_options =
+                            app.get_instance_types(instance.architecture.as_deref());
+                        initial_options.sort();
+
+                        // Trigger pricing fetch
+                        if let Some(az) = &instance.availability_zone {
+                            let az = az.clone();
+                            let client = app.ec2_client.clone();
+                            let tx = app.event_tx.clone();
+
+                            app.fetch_on_demand_prices();
+
+                            let tx_spot = tx.clone();
+                            tokio::spawn(async move {
+                                if let Ok(prices) = client.fetch_all_spot_prices(&az).await {
+                                    let _ = tx_spot.send(AppEvent::BulkSpotFetched(prices)).await;
+                                }
                             });
-                            *selected_index = if options.is_empty() { None } else { Some(0) };
+
+                            if app.spot_advisor.is_none()
+                                && let Some(source) = app.config.spot_advisor_source.clone()
+                            {
+                                let tx_advisor = tx.clone();
+                                tokio::spawn(async move {
+                                    if let Ok(advisor) =
+                                        spot_advisor::SpotAdvisor::load(&source).await
+                                    {
+                                        let _ = tx_advisor
+                                            .send(AppEvent::SpotAdvisorFetched(advisor))
+                                            .await;
+                                    }
+                                });
+                            }
                         }
+
+                        let default_type =
+                            app.config.default_instance_type.clone().unwrap_or_default();
+                        // Default: Sort by Name, Selection None
+                        initial_options.sort();
+                        let selected_index = None;
+
+                        app.state = AppState::SelectingType {
+                            input: default_type,
+                            options: initial_options,
+                            selected_index,
+
+                            // Enable default mode:
+                            // 1. List is sorted by Name (not Cost).
+                            // 2. Async price updates will NOT re-sort the list.
+                            // 3. User interaction (typing/navigating) will disable this mode.
+                            default_mode_active: true,
+                        };
                     }
                 }
-                AppEvent::BulkSpotFetched(prices) => {
-                    for (t, p) in prices {
-                        let entry = app.type_prices.entry(t.clone()).or_insert(app::Prices {
-                            on_demand: None,
-                            spot: None,
-                        });
-                        entry.spot = Some(p);
+                KeyCode::Char('s') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        request_action(app, PendingAction::Stop(instance.id), false);
                     }
                 }
-                AppEvent::InstancesUpdated => {
-                    // Trigger background refresh
-                    let client = app.ec2_client.clone();
+                KeyCode::Char('h') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        if !instance.hibernation_configured {
+                            app.state = AppState::Processing(format!(
+                                "Error: Hibernation is not configured for {}. It can only be enabled at launch.",
+                                instance.id
+                            ));
+                        } else if !instance.state.eq_ignore_ascii_case("running") {
+                            app.state = AppState::Processing(format!(
+                                "Error: Cannot hibernate instance {} because it is in state '{}'.",
+                                instance.id, instance.state
+                            ));
+                        } else {
+                            request_action(app, PendingAction::Hibernate(instance.id), false);
+                        }
+                    }
+                }
+                KeyCode::Char('x') | KeyCode::Char('t') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        request_action(
+                            app,
+                            PendingAction::ToggleProtection {
+                                instance_id: instance.id,
+                                stop: key.code == KeyCode::Char('x'),
+                            },
+                            false,
+                        );
+                    }
+                }
+                KeyCode::Char('S') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        request_action(app, PendingAction::Start(instance.id), false);
+                    }
+                }
+                KeyCode::Char('l') | KeyCode::Char('R') if app.credentials_problem.is_some() => {
+                    reauthenticate(app, tui, key.code == KeyCode::Char('l')).await?;
+                }
+                KeyCode::Char('C') => cancel_operation(app),
+                KeyCode::Char('L') => {
+                    app.state = AppState::AuditLog {
+                        records: None,
+                        instance_filter: String::new(),
+                        action_filter: None,
+                        selected_index: None,
+                    };
                     let tx = app.event_tx.clone();
                     tokio::spawn(async move {
-                        match client.list_instances().await {
-                            Ok(instances) => {
-                                let _ = tx.send(AppEvent::InstancesFetched(instances)).await;
+                        match audit::load().await {
+                            Ok(records) => {
+                                let _ = tx.send(AppEvent::AuditLoaded(records)).await;
                             }
                             Err(e) => {
-                                let _ = tx.send(AppEvent::Error(e.to_string())).await;
+                                let _ = tx
+                                    .send(AppEvent::Error(format!(
+                                        "Could not read audit log: {}",
+                                        e
+                                    )))
+                                    .await;
                             }
                         }
                     });
-
-                    // Reset to list if we were processing
-                    if let AppState::Processing(_) = app.state {
-                        app.state = AppState::List;
-                    }
                 }
-                AppEvent::Error(e) => {
-                    app.state = AppState::Processing(format!("Error: {}", e));
-                    // User needs to Esc to clear
-                }
-                AppEvent::Message(msg) => {
-                    app.state = AppState::Processing(msg);
+                KeyCode::Char('D') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        app.state = AppState::PermissionReport {
+                            instance_id: instance.id.clone(),
+                            checks: None,
+                        };
+                        let client = app.ec2_client.clone();
+                        let tx = app.event_tx.clone();
+                        let credit_spec = app
+                            .config
+                            .t_family_credit
+                            .clone()
+                            .unwrap_or("standard".to_string());
+                        tokio::spawn(async move {
+                            let checks = client
+                                .check_permissions(
+                                    &instance.id,
+                                    &instance.instance_type,
+                                    &credit_spec,
+                                )
+                                .await;
+                            let _ = tx
+                                .send(AppEvent::PermissionsChecked(instance.id, checks))
+                                .await;
+                        });
+                    }
                 }
-            }
-        }
-
-        if event::poll(Duration::from_millis(100))?
-            && let Event::Key(key) = event::read()?
-        {
-            if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
-                app.should_quit = true;
-            }
-
-            let current_state = app.state.clone();
-            match current_state {
-                AppState::List => {
-                    match key.code {
-                        KeyCode::Char('q') => app.should_quit = true,
-                        KeyCode::Down => app.next(),
-                        KeyCode::Up => app.previous(),
-                        KeyCode::PageDown => {
-                            let i = match app.list_state.selected() {
-                                Some(i) => {
-                                    (i + 20).min(app.filtered_instances.len().saturating_sub(1))
-                                }
-                                None => 0,
-                            };
-                            app.list_state.select(Some(i));
-                        }
-                        KeyCode::PageUp => {
-                            let i = match app.list_state.selected() {
-                                Some(i) => i.saturating_sub(20),
-                                None => 0,
+                KeyCode::Char('o') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        if let Some(cw) = app.cloudwatch_client.clone() {
+                            app.state = AppState::Recommendations {
+                                instance_id: instance.id.clone(),
+                                stats: None,
+                                recommendations: vec![],
+                                selected_index: None,
                             };
-                            app.list_state.select(Some(i));
-                        }
-                        KeyCode::Home => app.list_state.select(Some(0)),
-                        KeyCode::End => {
-                            let i = app.filtered_instances.len().saturating_sub(1);
-                            app.list_state.select(Some(i));
-                        }
-                        KeyCode::Char('f') => app.state = AppState::FilterInput,
-                        KeyCode::Char('c') => {
-                            if let Some(idx) = app.list_state.selected()
-                                && let Some(instance) = app.filtered_instances.get(idx)
-                            {
-                                let mut initial_options =
-                                    app.get_instance_types(instance.architecture.as_deref());
-                                initial_options.sort();
-
-                                // Trigger pricing fetch
-                                if let Some(az) = &instance.availability_zone {
-                                    let az = az.clone();
-                                    let client = app.ec2_client.clone();
-                                    let pricing_client = app.pricing_client.clone();
-                                    let tx = app.event_tx.clone();
-
-                                    if let Some(pc) = pricing_client
-                                        && app.type_prices.is_empty()
-                                    {
-                                        let tx_od = tx.clone();
-                                        tokio::spawn(async move {
-                                            if let Ok(prices) =
-                                                pc.fetch_all_on_demand_prices().await
-                                            {
-                                                let _ = tx_od
-                                                    .send(AppEvent::BulkOnDemandFetched(prices))
-                                                    .await;
-                                            }
-                                        });
-                                    }
-
-                                    let tx_spot = tx.clone();
-                                    tokio::spawn(async move {
-                                        if let Ok(prices) = client.fetch_all_spot_prices(&az).await
-                                        {
-                                            let _ = tx_spot
-                                                .send(AppEvent::BulkSpotFetched(prices))
-                                                .await;
-                                        }
-                                    });
-                                }
+                            app.fetch_on_demand_prices();
 
-                                let default_type =
-                                    app.config.default_instance_type.clone().unwrap_or_default();
-                                // Default: Sort by Name, Selection None
-                                initial_options.sort();
-                                let selected_index = None;
-
-                                app.state = AppState::SelectingType {
-                                    input: default_type,
-                                    options: initial_options,
-                                    selected_index,
-
-                                    // Enable default mode:
-                                    // 1. List is sorted by Name (not Cost).
-                                    // 2. Async price updates will NOT re-sort the list.
-                                    // 3. User interaction (typing/navigating) will disable this mode.
-                                    default_mode_active: true,
-                                };
-                            }
-                        }
-                        KeyCode::Char('s') => {
-                            if let Some(idx) = app.list_state.selected()
-                                && let Some(instance) = app.filtered_instances.get(idx).cloned()
-                            {
-                                app.state =
-                                    AppState::Processing(format!("Stopping {}...", instance.id));
-                                let tx = app.event_tx.clone();
-                                let client = app.ec2_client.clone();
-                                let instance_id = instance.id.clone();
-                                tokio::spawn(async move {
-                                    if let Err(e) = client.stop_instance(&instance_id).await {
+                            let tx = app.event_tx.clone();
+                            let days = app.config.rightsizing_lookback_days.unwrap_or(14);
+                            tokio::spawn(async move {
+                                match cw.fetch_utilization_stats(&instance.id, days).await {
+                                    Ok(stats) => {
                                         let _ = tx
-                                            .send(AppEvent::Error(format!("Stop failed: {}", e)))
+                                            .send(AppEvent::UtilizationFetched(instance.id, stats))
                                             .await;
-                                    } else {
+                                    }
+                                    Err(e) => {
                                         let _ = tx
-                                            .send(AppEvent::Message(
-                                                "Stopped. Refreshing...".to_string(),
-                                            ))
+                                            .send(AppEvent::Error(format!(
+                                                "Utilization fetch failed: {}",
+                                                e
+                                            )))
                                             .await;
-                                        let _ = tx.send(AppEvent::InstancesUpdated).await;
                                     }
-                                });
-                            }
+                                }
+                            });
+                        } else {
+                            app.state = AppState::Processing(
+                                "Error: CloudWatch client unavailable.".to_string(),
+                            );
                         }
-                        KeyCode::Char('S') => {
-                            if let Some(idx) = app.list_state.selected()
-                                && let Some(instance) = app.filtered_instances.get(idx).cloned()
-                            {
-                                app.state =
-                                    AppState::Processing(format!("Starting {}...", instance.id));
-                                let tx = app.event_tx.clone();
-                                let client = app.ec2_client.clone();
-                                let instance_id = instance.id.clone();
+                    }
+                }
+                KeyCode::Char('I') => {
+                    if let Some(cw) = app.cloudwatch_client.clone() {
+                        app.state = AppState::IdleInstances {
+                            candidates: vec![],
+                            selected_index: None,
+                            scanning: true,
+                        };
+                        app.fetch_on_demand_prices();
 
-                                tokio::spawn(async move {
-                                    if let Err(e) = client.start_instance(&instance_id).await {
-                                        let _ = tx
-                                            .send(AppEvent::Error(format!("Start failed: {}", e)))
-                                            .await;
-                                    } else {
+                        let running: Vec<InstanceInfo> = app
+                            .instances
+                            .iter()
+                            .filter(|i| i.state == "running")
+                            .cloned()
+                            .collect();
+                        let thresholds = idle::IdleThresholds::from_config(&app.config);
+                        let tx = app.event_tx.clone();
+                        tokio::spawn(async move {
+                            let mut set = tokio::task::JoinSet::new();
+                            for instance in running {
+                                let cw = cw.clone();
+                                set.spawn(
+                                    async move { cw.check_idle(&instance, &thresholds).await },
+                                );
+                            }
+
+                            let mut found = Vec::new();
+                            while let Some(res) = set.join_next().await {
+                                match res {
+                                    Ok(Ok(Some(candidate))) => found.push(candidate),
+                                    Ok(Ok(None)) => {}
+                                    Ok(Err(e)) => {
                                         let _ = tx
-                                            .send(AppEvent::Message(
-                                                "Started. Refreshing...".to_string(),
-                                            ))
+                                            .send(AppEvent::Error(format!(
+                                                "Idle scan failed: {}",
+                                                e
+                                            )))
                                             .await;
-                                        let _ = tx.send(AppEvent::InstancesUpdated).await;
+                                        return;
                                     }
-                                });
-                            }
-                        }
-                        KeyCode::Char('r') => {
-                            if let Some(idx) = app.list_state.selected()
-                                && let Some(instance) = app.filtered_instances.get(idx).cloned()
-                            {
-                                if instance.state.eq_ignore_ascii_case("running") {
-                                    app.state = AppState::ConfirmReboot(instance.id.clone());
-                                } else {
-                                    app.state = AppState::Processing(format!(
-                                        "Error: Cannot reboot instance {} because it is in state '{}'.",
-                                        instance.id, instance.state
-                                    ));
+                                    Err(_) => {}
                                 }
                             }
-                        }
-                        _ => {}
+                            found.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+                            let _ = tx.send(AppEvent::IdleScanned(found)).await;
+                        });
+                    } else {
+                        app.state = AppState::Processing(
+                            "Error: CloudWatch client unavailable.".to_string(),
+                        );
                     }
                 }
-                AppState::ConfirmReboot(instance_id) => {
-                    let instance_id = instance_id.clone();
-                    match key.code {
-                        KeyCode::Char('y') | KeyCode::Enter => {
-                            app.state =
-                                AppState::Processing(format!("Rebooting {}...", instance_id));
-                            let tx = app.event_tx.clone();
-                            let client = app.ec2_client.clone();
-                            tokio::spawn(async move {
-                                if let Err(e) = client.reboot_instance(&instance_id).await {
-                                    let _ = tx
-                                        .send(AppEvent::Error(format!("Reboot failed: {}", e)))
-                                        .await;
-                                } else {
-                                    let _ =
-                                        tx.send(AppEvent::Message("Rebooted. (Note: AWS Runtime does not reset)".to_string())).await;
-                                    let _ = tx.send(AppEvent::InstancesUpdated).await;
-                                }
-                            });
-                        }
-                        KeyCode::Char('f') => {
+                KeyCode::Char('p') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        if instance.is_spot() {
+                            app.state = AppState::SpotDetails {
+                                instance_id: instance.id.clone(),
+                                request: app.spot_requests.get(&instance.id).cloned(),
+                            };
+                            app.fetch_spot_request(&instance);
+                        } else {
                             app.state = AppState::Processing(format!(
-                                "Forced Rebooting {}...",
-                                instance_id
+                                "Error: {} is an on-demand instance.",
+                                instance.id
                             ));
-                            let tx = app.event_tx.clone();
-                            let client = app.ec2_client.clone();
-                            tokio::spawn(async move {
-                                if let Err(e) = client.force_reboot_instance(&instance_id).await {
-                                    let _ = tx
-                                        .send(AppEvent::Error(format!(
-                                            "Forced Reboot failed: {}",
-                                            e
-                                        )))
-                                        .await;
-                                } else {
-                                    let _ = tx
-                                        .send(AppEvent::Message(
-                                            "Forced Reboot Complete.".to_string(),
-                                        ))
-                                        .await;
-                                    let _ = tx.send(AppEvent::InstancesUpdated).await;
-                                }
-                            });
-                        }
-                        KeyCode::Char('n') | KeyCode::Esc => {
-                            app.state = AppState::List;
                         }
-                        _ => {}
                     }
                 }
-                AppState::FilterInput => match key.code {
-                    KeyCode::Esc => {
-                        app.state = AppState::List;
-                    }
-                    KeyCode::Enter => {
-                        app.state = AppState::List;
-                    }
-                    KeyCode::Char(c) => {
-                        app.filter.push(c);
-                        app.update_filter();
+                KeyCode::Char('r') => {
+                    if let Some(idx) = app.list_state.selected()
+                        && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                    {
+                        let reboot = PendingAction::Reboot {
+                            instance_id: instance.id.clone(),
+                            force: false,
+                        };
+                        if !instance.state.eq_ignore_ascii_case("running") {
+                            app.state = AppState::Processing(format!(
+                                "Error: Cannot reboot instance {} because it is in state '{}'.",
+                                instance.id, instance.state
+                            ));
+                        } else if let Some(reason) = refusal(app, &reboot) {
+                            app.state = AppState::Processing(reason);
+                        } else {
+                            app.state = AppState::ConfirmReboot(instance.id.clone());
+                        }
                     }
-                    KeyCode::Backspace => {
-                        app.filter.pop();
-                        app.update_filter();
+                }
+                _ => {}
+            }
+
+            // Follow the selection with the metrics pane
+            if app.show_metrics {
+                let selected_id = app
+                    .list_state
+                    .selected()
+                    .and_then(|i| app.filtered_instances.get(i))
+                    .map(|i| i.id.clone());
+                if selected_id.is_some() && selected_id != app.metrics_requested_for {
+                    app.metrics = None;
+                    app.fetch_metrics();
+                }
+            }
+        }
+        AppState::ConfirmReboot(instance_id) => {
+            let instance_id = instance_id.clone();
+            match key.code {
+                KeyCode::Char('y') | KeyCode::Enter | KeyCode::Char('f') => {
+                    let force = key.code == KeyCode::Char('f');
+                    request_action(app, PendingAction::Reboot { instance_id, force }, true);
+                }
+                KeyCode::Char('n') | KeyCode::Esc => {
+                    app.state = AppState::List;
+                }
+                _ => {}
+            }
+        }
+        AppState::ConfirmAction {
+            action,
+            expected,
+            input,
+        } => {
+            let (action, expected, mut input) = (action.clone(), expected.clone(), input.clone());
+            let typing = expected.is_some();
+            match key.code {
+                KeyCode::Esc => app.state = AppState::List,
+                KeyCode::Char('n') if !typing => app.state = AppState::List,
+                KeyCode::Char('y') if !typing => execute_action(app, action),
+                KeyCode::Enter => match expected {
+                    Some(expected) if input != expected => {
+                        app.state = AppState::Processing(format!(
+                            "Error: '{}' does not match '{}'. Nothing was changed.",
+                            input, expected
+                        ));
                     }
-                    _ => {}
+                    _ => execute_action(app, action),
                 },
-                AppState::SelectingType {
-                    mut input,
+                KeyCode::Char(c) if typing => {
+                    input.push(c);
+                    app.state = AppState::ConfirmAction {
+                        action,
+                        expected,
+                        input,
+                    };
+                }
+                KeyCode::Backspace if typing => {
+                    input.pop();
+                    app.state = AppState::ConfirmAction {
+                        action,
+                        expected,
+                        input,
+                    };
+                }
+                _ => {}
+            }
+        }
+        AppState::FilterInput => match key.code {
+            KeyCode::Esc => {
+                app.state = AppState::List;
+            }
+            KeyCode::Enter => {
+                app.state = AppState::List;
+            }
+            KeyCode::Char(c) => {
+                app.filter.push(c);
+                app.update_filter();
+            }
+            KeyCode::Backspace => {
+                app.filter.pop();
+                app.update_filter();
+            }
+            _ => {}
+        },
+        AppState::SelectingType {
+            mut input,
+            options,
+            mut selected_index,
+            mut default_mode_active,
+        } => match key.code {
+            KeyCode::Esc => {
+                app.state = AppState::List;
+            }
+            KeyCode::Up => {
+                if default_mode_active {
+                    input.clear();
+                    selected_index = Some(0);
+                    default_mode_active = false;
+                } else if let Some(idx) = selected_index
+                    && idx > 0
+                {
+                    selected_index = Some(idx - 1);
+                }
+                app.state = AppState::SelectingType {
+                    input,
                     options,
-                    mut selected_index,
-                    mut default_mode_active,
-                } => {
-                    match key.code {
-                        KeyCode::Esc => {
-                            app.state = AppState::List;
-                        }
-                        KeyCode::Up => {
-                            if default_mode_active {
-                                input.clear();
-                                selected_index = Some(0);
-                                default_mode_active = false;
-                            } else if let Some(idx) = selected_index
-                                && idx > 0
-                            {
-                                selected_index = Some(idx - 1);
-                            }
-                            app.state = AppState::SelectingType {
-                                input,
-                                options,
-                                selected_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::Down => {
-                            if default_mode_active {
-                                input.clear();
-                                selected_index = Some(0);
-                                default_mode_active = false;
-                            } else if let Some(idx) = selected_index
-                                && idx < options.len().saturating_sub(1)
-                            {
-                                selected_index = Some(idx + 1);
-                            }
-                            app.state = AppState::SelectingType {
-                                input,
-                                options,
-                                selected_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::PageUp => {
-                            if default_mode_active {
-                                input.clear();
-                                selected_index = Some(0);
-                                default_mode_active = false;
-                            } else if let Some(idx) = selected_index {
-                                let new_idx = idx.saturating_sub(20);
-                                selected_index = Some(new_idx);
-                            }
-                            app.state = AppState::SelectingType {
-                                input,
-                                options,
-                                selected_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::PageDown => {
-                            if default_mode_active {
-                                input.clear();
-                                selected_index = Some(0);
-                                default_mode_active = false;
-                            } else if let Some(idx) = selected_index {
-                                let new_idx = (idx + 20).min(options.len().saturating_sub(1));
-                                selected_index = Some(new_idx);
-                            }
-                            app.state = AppState::SelectingType {
-                                input,
-                                options,
-                                selected_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::Home => {
-                            if default_mode_active {
-                                input.clear();
-                                default_mode_active = false;
-                            }
-                            selected_index = Some(0);
-                            app.state = AppState::SelectingType {
-                                input,
-                                options,
-                                selected_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::End => {
-                            if default_mode_active {
-                                input.clear();
-                                default_mode_active = false;
-                            }
-                            selected_index = Some(options.len().saturating_sub(1));
-                            app.state = AppState::SelectingType {
-                                input,
-                                options,
-                                selected_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::Char(c) => {
-                            if default_mode_active {
-                                input.clear();
-                                default_mode_active = false;
-                            }
-                            input.push(c);
-                            let arch_string = app.get_selected_architecture();
-                            let all_types = app.get_instance_types(arch_string.as_deref());
-
-                            let mut new_options: Vec<String> = all_types
-                                .into_iter()
-                                .filter(|t| t.contains(input.as_str()))
-                                .collect();
-
-                            new_options.sort_by(|a, b| {
-                                let price_a = app
-                                    .type_prices
-                                    .get(a)
-                                    .and_then(|p| p.on_demand)
-                                    .unwrap_or(f64::MAX);
-                                let price_b = app
-                                    .type_prices
-                                    .get(b)
-                                    .and_then(|p| p.on_demand)
-                                    .unwrap_or(f64::MAX);
-                                price_a
-                                    .partial_cmp(&price_b)
-                                    .unwrap_or(std::cmp::Ordering::Equal)
-                                    .then_with(|| a.cmp(b))
-                            });
+                    selected_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::Down => {
+                if default_mode_active {
+                    input.clear();
+                    selected_index = Some(0);
+                    default_mode_active = false;
+                } else if let Some(idx) = selected_index
+                    && idx < options.len().saturating_sub(1)
+                {
+                    selected_index = Some(idx + 1);
+                }
+                app.state = AppState::SelectingType {
+                    input,
+                    options,
+                    selected_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::PageUp => {
+                if default_mode_active {
+                    input.clear();
+                    selected_index = Some(0);
+                    default_mode_active = false;
+                } else if let Some(idx) = selected_index {
+                    let new_idx = idx.saturating_sub(20);
+                    selected_index = Some(new_idx);
+                }
+                app.state = AppState::SelectingType {
+                    input,
+                    options,
+                    selected_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::PageDown => {
+                if default_mode_active {
+                    input.clear();
+                    selected_index = Some(0);
+                    default_mode_active = false;
+                } else if let Some(idx) = selected_index {
+                    let new_idx = (idx + 20).min(options.len().saturating_sub(1));
+                    selected_index = Some(new_idx);
+                }
+                app.state = AppState::SelectingType {
+                    input,
+                    options,
+                    selected_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::Home => {
+                if default_mode_active {
+                    input.clear();
+                    default_mode_active = false;
+                }
+                selected_index = Some(0);
+                app.state = AppState::SelectingType {
+                    input,
+                    options,
+                    selected_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::End => {
+                if default_mode_active {
+                    input.clear();
+                    default_mode_active = false;
+                }
+                selected_index = Some(options.len().saturating_sub(1));
+                app.state = AppState::SelectingType {
+                    input,
+                    options,
+                    selected_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::Char(c) => {
+                if default_mode_active {
+                    input.clear();
+                    default_mode_active = false;
+                }
+                input.push(c);
+                let arch_string = app.get_selected_architecture();
+                let all_types = app.get_instance_types(arch_string.as_deref());
 
-                            let new_index = if new_options.is_empty() {
-                                None
-                            } else {
-                                Some(0)
-                            };
-                            app.state = AppState::SelectingType {
-                                input,
-                                options: new_options,
-                                selected_index: new_index,
-                                default_mode_active,
-                            };
-                        }
-                        KeyCode::Backspace => {
-                            if !default_mode_active {
-                                input.pop();
-                                let arch_string = app.get_selected_architecture();
-                                let mut all_types = app.get_instance_types(arch_string.as_deref());
-                                let default_type =
-                                    app.config.default_instance_type.as_deref().unwrap_or("");
-
-                                if input.is_empty() {
-                                    input = default_type.to_string();
-                                    all_types.sort();
-                                    app.state = AppState::SelectingType {
-                                        input,
-                                        options: all_types,
-                                        selected_index: None,
-                                        default_mode_active: true,
-                                    };
-                                } else {
-                                    let mut new_options: Vec<String> = all_types
-                                        .into_iter()
-                                        .filter(|t| t.contains(input.as_str()))
-                                        .collect();
-
-                                    new_options.sort_by(|a, b| {
-                                        let price_a = app
-                                            .type_prices
-                                            .get(a)
-                                            .and_then(|p| p.on_demand)
-                                            .unwrap_or(f64::MAX);
-                                        let price_b = app
-                                            .type_prices
-                                            .get(b)
-                                            .and_then(|p| p.on_demand)
-                                            .unwrap_or(f64::MAX);
-                                        price_a
-                                            .partial_cmp(&price_b)
-                                            .unwrap_or(std::cmp::Ordering::Equal)
-                                            .then_with(|| a.cmp(b))
-                                    });
-
-                                    let new_index = if new_options.is_empty() {
-                                        None
-                                    } else {
-                                        Some(0)
-                                    };
-                                    app.state = AppState::SelectingType {
-                                        input,
-                                        options: new_options,
-                                        selected_index: new_index,
-                                        default_mode_active,
-                                    };
-                                }
-                            }
-                        }
-                        KeyCode::Enter => {
-                            let selected_option = if options.len() == 1 {
-                                options.first().cloned()
-                            } else if let Some(i) = selected_index {
-                                options.get(i).cloned()
-                            } else if options.contains(&input) {
-                                Some(input.clone())
-                            } else {
-                                None
-                            };
+                let mut new_options: Vec<String> = all_types
+                    .into_iter()
+                    .filter(|t| t.contains(input.as_str()))
+                    .collect();
 
-                            if let Some(new_type) = selected_option
-                                && let Some(idx) = app.list_state.selected()
-                                && let Some(instance) = app.filtered_instances.get(idx).cloned()
-                            {
-                                if new_type == instance.instance_type {
-                                    app.state = AppState::List;
-                                } else {
-                                    app.state = AppState::Processing(format!(
-                                        "Stopping {}...",
-                                        instance.id
-                                    ));
-
-                                    let client = app.ec2_client.clone();
-                                    let tx = app.event_tx.clone();
-                                    let instance_id = instance.id.clone();
-                                    let credit_spec = app
-                                        .config
-                                        .t_family_credit
-                                        .clone()
-                                        .unwrap_or("standard".to_string());
-
-                                    tokio::spawn(async move {
-                                        // 1. Stop
-                                        if let Err(e) = client.stop_instance(&instance_id).await {
-                                            let _ = tx
-                                                .send(AppEvent::Error(format!(
-                                                    "Stop failed: {}",
-                                                    e
-                                                )))
-                                                .await;
-                                            return;
-                                        }
+                new_options.sort_by(|a, b| {
+                    let price_a = app
+                        .type_prices
+                        .get(a)
+                        .and_then(|p| p.on_demand)
+                        .unwrap_or(f64::MAX);
+                    let price_b = app
+                        .type_prices
+                        .get(b)
+                        .and_then(|p| p.on_demand)
+                        .unwrap_or(f64::MAX);
+                    price_a
+                        .partial_cmp(&price_b)
+                        .unwrap_or(std::cmp::Ordering::Equal)
+                        .then_with(|| a.cmp(b))
+                });
 
-                                        let _ = tx
-                                            .send(AppEvent::Message(
-                                                "Waiting for stop...".to_string(),
-                                            ))
-                                            .await;
-                                        if let Err(e) = client
-                                            .wait_until_stopped(
-                                                &instance_id,
-                                                std::time::Duration::from_secs(300),
-                                            )
-                                            .await
-                                        {
-                                            let _ = tx
-                                                .send(AppEvent::Error(format!(
-                                                    "Wait failed: {}",
-                                                    e
-                                                )))
-                                                .await;
-                                            return;
-                                        }
-
-                                        // 2. Modify
-                                        let _ = tx
-                                            .send(AppEvent::Message(format!(
-                                                "Changing to {}...",
-                                                new_type
-                                            )))
-                                            .await;
-                                        if let Err(e) = client
-                                            .modify_instance_type(&instance_id, &new_type)
-                                            .await
-                                        {
-                                            let _ = tx
-                                                .send(AppEvent::Error(format!(
-                                                    "Modify failed: {:?}",
-                                                    e
-                                                )))
-                                                .await;
-                                            return;
-                                        }
-
-                                        // 3. T-credit
-                                        if new_type.starts_with("t") {
-                                            let _ = tx
-                                                .send(AppEvent::Message(
-                                                    "Setting credit spec...".to_string(),
-                                                ))
-                                                .await;
-                                            if let Err(e) = client
-                                                .modify_credit_specification(
-                                                    &instance_id,
-                                                    &credit_spec,
-                                                )
-                                                .await
-                                            {
-                                                let _ = tx
-                                                    .send(AppEvent::Error(format!(
-                                                        "Credit spec failed: {:?}",
-                                                        e
-                                                    )))
-                                                    .await;
-                                            }
-                                        }
-
-                                        // 4. Start
-                                        let _ = tx
-                                            .send(AppEvent::Message("Starting...".to_string()))
-                                            .await;
-                                        if let Err(e) = client.start_instance(&instance_id).await {
-                                            let _ = tx
-                                                .send(AppEvent::Error(format!(
-                                                    "Start failed: {}",
-                                                    e
-                                                )))
-                                                .await;
-                                            return;
-                                        }
+                let new_index = if new_options.is_empty() {
+                    None
+                } else {
+                    Some(0)
+                };
+                app.state = AppState::SelectingType {
+                    input,
+                    options: new_options,
+                    selected_index: new_index,
+                    default_mode_active,
+                };
+            }
+            KeyCode::Backspace => {
+                if !default_mode_active {
+                    input.pop();
+                    let arch_string = app.get_selected_architecture();
+                    let mut all_types = app.get_instance_types(arch_string.as_deref());
+                    let default_type = app.config.default_instance_type.as_deref().unwrap_or("");
 
-                                        let _ = tx
-                                            .send(AppEvent::Message(
-                                                "Done. Refreshing...".to_string(),
-                                            ))
-                                            .await;
-                                        let _ = tx.send(AppEvent::InstancesUpdated).await;
-                                    });
-                                }
-                            }
-                        }
-                        _ => {}
+                    if input.is_empty() {
+                        input = default_type.to_string();
+                        all_types.sort();
+                        app.state = AppState::SelectingType {
+                            input,
+                            options: all_types,
+                            selected_index: None,
+                            default_mode_active: true,
+                        };
+                    } else {
+                        let mut new_options: Vec<String> = all_types
+                            .into_iter()
+                            .filter(|t| t.contains(input.as_str()))
+                            .collect();
+
+                        new_options.sort_by(|a, b| {
+                            let price_a = app
+                                .type_prices
+                                .get(a)
+                                .and_then(|p| p.on_demand)
+                                .unwrap_or(f64::MAX);
+                            let price_b = app
+                                .type_prices
+                                .get(b)
+                                .and_then(|p| p.on_demand)
+                                .unwrap_or(f64::MAX);
+                            price_a
+                                .partial_cmp(&price_b)
+                                .unwrap_or(std::cmp::Ordering::Equal)
+                                .then_with(|| a.cmp(b))
+                        });
+
+                        let new_index = if new_options.is_empty() {
+                            None
+                        } else {
+                            Some(0)
+                        };
+                        app.state = AppState::SelectingType {
+                            input,
+                            options: new_options,
+                            selected_index: new_index,
+                            default_mode_active,
+                        };
                     }
                 }
-                AppState::Processing(_) => {
-                    if key.code == KeyCode::Esc || key.code == KeyCode::Enter {
+            }
+            KeyCode::Enter => {
+                let selected_option = if options.len() == 1 {
+                    options.first().cloned()
+                } else if let Some(i) = selected_index {
+                    options.get(i).cloned()
+                } else if options.contains(&input) {
+                    Some(input.clone())
+                } else {
+                    None
+                };
+
+                if let Some(new_type) = selected_option
+                    && let Some(idx) = app.list_state.selected()
+                    && let Some(instance) = app.filtered_instances.get(idx).cloned()
+                {
+                    if new_type == instance.instance_type {
                         app.state = AppState::List;
+                    } else {
+                        request_action(
+                            app,
+                            PendingAction::Resize {
+                                instance_id: instance.id,
+                                new_type,
+                            },
+                            false,
+                        );
+                    }
+                }
+            }
+            _ => {}
+        },
+        AppState::Recommendations {
+            instance_id,
+            stats,
+            recommendations,
+            mut selected_index,
+        } => match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => app.state = AppState::List,
+            KeyCode::Up => {
+                if let Some(i) = selected_index
+                    && i > 0
+                {
+                    selected_index = Some(i - 1);
+                }
+                app.state = AppState::Recommendations {
+                    instance_id,
+                    stats,
+                    recommendations,
+                    selected_index,
+                };
+            }
+            KeyCode::Down => {
+                if let Some(i) = selected_index
+                    && i < recommendations.len().saturating_sub(1)
+                {
+                    selected_index = Some(i + 1);
+                }
+                app.state = AppState::Recommendations {
+                    instance_id,
+                    stats,
+                    recommendations,
+                    selected_index,
+                };
+            }
+            KeyCode::Enter => {
+                if let Some(rec) = selected_index.and_then(|i| recommendations.get(i))
+                    && let Some(instance) =
+                        app.instances.iter().find(|i| i.id == instance_id).cloned()
+                {
+                    request_action(
+                        app,
+                        PendingAction::Resize {
+                            instance_id: instance.id,
+                            new_type: rec.instance_type.clone(),
+                        },
+                        false,
+                    );
+                }
+            }
+            _ => {}
+        },
+        AppState::AuditLog {
+            records,
+            mut instance_filter,
+            mut action_filter,
+            mut selected_index,
+        } => {
+            let visible = records.as_ref().map_or(0, |r| {
+                r.iter()
+                    .filter(|r| {
+                        r.matches(&instance_filter, action_filter.map(|i| audit::ACTIONS[i]))
+                    })
+                    .count()
+            });
+            let mut filter_changed = false;
+            let mut return_to_list = false;
+            match key.code {
+                KeyCode::Esc => {
+                    app.state = AppState::List;
+                    return_to_list = true;
+                }
+                KeyCode::Up => {
+                    if let Some(i) = selected_index
+                        && i > 0
+                    {
+                        selected_index = Some(i - 1);
+                    }
+                }
+                KeyCode::Down => {
+                    if let Some(i) = selected_index
+                        && i < visible.saturating_sub(1)
+                    {
+                        selected_index = Some(i + 1);
+                    }
+                }
+                KeyCode::Tab => {
+                    action_filter = match action_filter {
+                        None => Some(0),
+                        Some(i) if i + 1 < audit::ACTIONS.len() => Some(i + 1),
+                        Some(_) => None,
+                    };
+                    filter_changed = true;
+                }
+                KeyCode::Backspace => {
+                    instance_filter.pop();
+                    filter_changed = true;
+                }
+                KeyCode::Char(c) => {
+                    instance_filter.push(c);
+                    filter_changed = true;
+                }
+                _ => {}
+            }
+            if filter_changed {
+                selected_index = Some(0);
+            }
+            if !return_to_list {
+                app.state = AppState::AuditLog {
+                    records,
+                    instance_filter,
+                    action_filter,
+                    selected_index,
+                };
+            }
+        }
+        AppState::IdleInstances {
+            mut candidates,
+            mut selected_index,
+            scanning,
+        } => {
+            let mut return_to_list = false;
+            match key.code {
+                KeyCode::Esc | KeyCode::Char('q') => {
+                    app.state = AppState::List;
+                    return_to_list = true;
+                }
+                KeyCode::Up => {
+                    if let Some(i) = selected_index
+                        && i > 0
+                    {
+                        selected_index = Some(i - 1);
                     }
                 }
+                KeyCode::Down => {
+                    if let Some(i) = selected_index
+                        && i < candidates.len().saturating_sub(1)
+                    {
+                        selected_index = Some(i + 1);
+                    }
+                }
+                KeyCode::Char(' ') => {
+                    if let Some(c) = selected_index.and_then(|i| candidates.get_mut(i)) {
+                        c.marked = !c.marked;
+                    }
+                }
+                KeyCode::Char('a') => {
+                    let mark = !candidates.iter().all(|c| c.marked);
+                    for c in candidates.iter_mut() {
+                        c.marked = mark;
+                    }
+                }
+                KeyCode::Char('s') => {
+                    let ids: Vec<String> = candidates
+                        .iter()
+                        .filter(|c| c.marked)
+                        .map(|c| c.instance_id.clone())
+                        .collect();
+                    if !ids.is_empty() {
+                        request_action(app, PendingAction::BulkStop(ids), false);
+                        return_to_list = true;
+                    }
+                }
+                _ => {}
+            }
+            if !return_to_list {
+                app.state = AppState::IdleInstances {
+                    candidates,
+                    selected_index,
+                    scanning,
+                };
             }
         }
+        AppState::SpotDetails { .. } | AppState::PermissionReport { .. } => {
+            if matches!(key.code, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) {
+                app.state = AppState::List;
+            }
+        }
+        AppState::Processing(_) => {
+            if key.code == KeyCode::Esc || key.code == KeyCode::Enter {
+                app.state = AppState::List;
+                app.error_detail = None;
+            } else if key.code == KeyCode::Char('e') && app.error_detail.is_some() {
+                app.error_expanded = !app.error_expanded;
+            } else if key.code == KeyCode::Char('C') {
+                cancel_operation(app);
+            }
+        }
+    }
+    Ok(())
+}
 
-        if app.should_quit {
-            break;
+/// Applies a result reported by a background task.
+fn handle_event(app: &mut App, event: AppEvent) {
+    match event {
+        AppEvent::InstancesFetched(instances) => {
+            app.instances = instances;
+            app.update_filter();
+            app.fetch_missing_protection();
+            app.fetch_status_checks();
+            app.last_refreshed = Some(chrono::Local::now().format("%H:%M:%S").to_string());
+        }
+        AppEvent::StatusChecksFetched(checks) => app.status_checks = checks,
+        AppEvent::InstanceTypesFetched(types) => {
+            app.instance_type_map = types.iter().map(|t| (t.name.clone(), t.clone())).collect();
+            app.instance_types = types;
         }
+        AppEvent::Loaded(load) => app.loading.retain(|l| *l != load),
+
+        AppEvent::BulkOnDemandFetched(prices) => {
+            for (t, p) in prices {
+                let entry = app.type_prices.entry(t.clone()).or_insert(app::Prices {
+                    on_demand: None,
+                    spot: None,
+                });
+                entry.on_demand = Some(p);
+            }
+            if let AppState::SelectingType {
+                options,
+                selected_index,
+                default_mode_active,
+                ..
+            } = &mut app.state
+            {
+                // Only re-sort by price if the user has engaged with the input (default mode is NOT active).
+                // If default_mode_active is true, we keep the list sorted alphabetically to avoid confusing the user
+                // with a re-order while they are viewing the default suggestion.
+                if !*default_mode_active {
+                    let type_prices = &app.type_prices;
+                    options.sort_by(|a, b| {
+                        let price_a = type_prices
+                            .get(a)
+                            .and_then(|p| p.on_demand)
+                            .unwrap_or(f64::MAX);
+                        let price_b = type_prices
+                            .get(b)
+                            .and_then(|p| p.on_demand)
+                            .unwrap_or(f64::MAX);
+                        price_a
+                            .partial_cmp(&price_b)
+                            .unwrap_or(std::cmp::Ordering::Equal)
+                            .then_with(|| a.cmp(b))
+                    });
+                    *selected_index = if options.is_empty() { None } else { Some(0) };
+                }
+            }
+            app.refresh_recommendations();
+        }
+        AppEvent::BulkSpotFetched(prices) => {
+            for (t, p) in prices {
+                let entry = app.type_prices.entry(t.clone()).or_insert(app::Prices {
+                    on_demand: None,
+                    spot: None,
+                });
+                entry.spot = Some(p);
+            }
+        }
+        AppEvent::SpotAdvisorFetched(advisor) => {
+            app.spot_advisor = Some(advisor);
+        }
+        AppEvent::MetricsFetched(metrics) => {
+            // Drop late results for an instance that is no longer selected
+            if app.metrics_requested_for.as_deref() == Some(metrics.instance_id.as_str()) {
+                app.metrics = Some(metrics);
+            }
+        }
+        AppEvent::UtilizationFetched(instance_id, fetched) => {
+            if let AppState::Recommendations {
+                instance_id: shown_id,
+                stats,
+                ..
+            } = &mut app.state
+                && *shown_id == instance_id
+            {
+                *stats = Some(fetched);
+                app.refresh_recommendations();
+            }
+        }
+        AppEvent::IdleScanned(found) => {
+            if let AppState::IdleInstances {
+                candidates,
+                selected_index,
+                scanning,
+            } = &mut app.state
+            {
+                *candidates = found;
+                *selected_index = if candidates.is_empty() { None } else { Some(0) };
+                *scanning = false;
+            }
+        }
+        AppEvent::SpotRequestFetched(instance_id, request) => {
+            if let AppState::SpotDetails {
+                instance_id: shown_id,
+                request: shown_request,
+            } = &mut app.state
+                && *shown_id == instance_id
+            {
+                *shown_request = Some(request.clone());
+            }
+            app.spot_requests.insert(instance_id, request);
+        }
+        AppEvent::ProtectionFetched(instance_id, protection) => {
+            app.protection.insert(instance_id, Some(protection));
+        }
+        AppEvent::PermissionsChecked(instance_id, results) => {
+            if let AppState::PermissionReport {
+                instance_id: shown_id,
+                checks,
+            } = &mut app.state
+                && *shown_id == instance_id
+            {
+                *checks = Some(results);
+            }
+        }
+        AppEvent::AuditLoaded(loaded) => {
+            if let AppState::AuditLog {
+                records,
+                selected_index,
+                ..
+            } = &mut app.state
+            {
+                *selected_index = if loaded.is_empty() { None } else { Some(0) };
+                *records = Some(loaded);
+            }
+        }
+        AppEvent::InstancesUpdated => {
+            // Trigger background refresh
+            spawn_refresh(app);
+
+            // Reset to list if we were processing
+            if let AppState::Processing(_) = app.state {
+                app.state = AppState::List;
+                app.error_detail = None;
+            }
+        }
+        AppEvent::Error(e) => {
+            app.state = AppState::Processing(format!("Error: {}", e));
+            app.error_detail = None;
+            // User needs to Esc to clear
+        }
+        AppEvent::Failed(e) => {
+            // Background calls failing on credentials only raise the banner, so the
+            // refresh doesn't pop the same error on every tick
+            let background = !matches!(app.state, AppState::Processing(_));
+            if e.kind.is_credentials() {
+                app.credentials_problem = Some(e.kind);
+            }
+            if !(background && e.kind.is_credentials()) {
+                app.state = AppState::Processing(format!("Error: {}", e));
+                app.error_detail = Some(e.detail);
+                app.error_expanded = false;
+            }
+        }
+        AppEvent::Message(msg) => {
+            app.state = AppState::Processing(msg);
+        }
+        AppEvent::Progress(instance_id, step) => {
+            app.operations.set_step(&instance_id, &step);
+            // Only moves a visible progress popup along; a hidden one stays in the side panel
+            if let AppState::Processing(msg) = &app.state
+                && !msg.starts_with("Error")
+            {
+                app.state = AppState::Processing(step);
+            }
+        }
+        AppEvent::OperationFinished(id) => finish_operation(app, id),
     }
+}
 
-    // Restore terminal handled by Tui Drop
+/// Lists instances in the background and reports them as `InstancesFetched`.
+fn spawn_refresh(app: &App) {
+    let client = app.ec2_client.clone();
+    let tx = app.event_tx.clone();
+    tokio::spawn(async move {
+        match client.list_instances().await {
+            Ok(instances) => {
+                let _ = tx.send(AppEvent::InstancesFetched(instances)).await;
+            }
+            Err(e) => {
+                let _ = tx
+                    .send(AppEvent::Failed(FriendlyError::new("Refresh failed", &e)))
+                    .await;
+            }
+        }
+    });
+}
 
-    // Save config on exit
-    app.config.filter = Some(app.filter.clone());
-    app.config.save().await?;
+/// Optionally runs the login command with the terminal handed back to it, then rebuilds the
+/// AWS clients and resumes auto-refresh if the new credentials work.
+async fn reauthenticate(app: &mut App, tui: &mut Tui, login: bool) -> Result<()> {
+    if login {
+        let command = app
+            .config
+            .login_command
+            .clone()
+            .unwrap_or("aws sso login".to_string());
+        tui.suspend()?;
+        println!("Running `{}`...", command);
+        let mut parts = command.split_whitespace();
+        let status = std::process::Command::new(parts.next().unwrap_or("aws"))
+            .args(parts)
+            .status();
+        tui.resume()?;
+
+        match status {
+            Ok(status) if status.success() => {}
+            Ok(status) => {
+                app.state =
+                    AppState::Processing(format!("Error: `{}` exited with {}", command, status));
+                return Ok(());
+            }
+            Err(e) => {
+                app.state =
+                    AppState::Processing(format!("Error: Could not run `{}`: {}", command, e));
+                return Ok(());
+            }
+        }
+    }
 
+    match app.reload_clients().await {
+        Ok(()) => {
+            app.state =
+                AppState::Processing("Credentials reloaded. Auto-refresh resumed.".to_string());
+        }
+        Err(e) => {
+            let e = FriendlyError::new("Reload failed", &e);
+            app.state = AppState::Processing(format!("Error: {}", e));
+            app.error_detail = Some(e.detail);
+            app.error_expanded = false;
+        }
+    }
     Ok(())
 }
+
+/// Entry point for every mutating action: checks read-only mode, stop protection and the
+/// configured policies, then runs `action`, asks for confirmation, or refuses. `confirmed` is
+/// set when the user already answered a yes/no prompt for it (e.g. the reboot dialog).
+fn request_action(app: &mut App, action: PendingAction, confirmed: bool) {
+    if let Some(reason) = refusal(app, &action) {
+        app.state = AppState::Processing(reason);
+        return;
+    }
+    if let PendingAction::Stop(id)
+    | PendingAction::Hibernate(id)
+    | PendingAction::Resize {
+        instance_id: id, ..
+    } = &action
+        && app.stop_protected(id)
+    {
+        let hint = if matches!(action, PendingAction::Resize { .. }) {
+            "Resizing requires a stop; press x to disable it first."
+        } else {
+            "Press x to disable it first."
+        };
+        app.state = AppState::Processing(format!(
+            "Error: Stop protection is enabled for {}. {}",
+            id, hint
+        ));
+        return;
+    }
+
+    let (requirement, _) = app.policy_requirement(&action);
+    match requirement {
+        policy::Requirement::TypeName => {
+            // Bulk actions are confirmed by typing the number of instances
+            let expected = match action.instance_ids().as_slice() {
+                [id] => app
+                    .instances
+                    .iter()
+                    .find(|i| i.id == *id)
+                    .and_then(|i| i.name.clone())
+                    .unwrap_or_else(|| id.to_string()),
+                ids => ids.len().to_string(),
+            };
+            app.state = AppState::ConfirmAction {
+                action,
+                expected: Some(expected),
+                input: String::new(),
+            };
+        }
+        policy::Requirement::Confirm if !confirmed => {
+            app.state = AppState::ConfirmAction {
+                action,
+                expected: None,
+                input: String::new(),
+            };
+        }
+        _ => execute_action(app, action),
+    }
+}
+
+/// The error to show if read-only mode, or a policy on any of its targets, forbids `action`.
+fn refusal(app: &App, action: &PendingAction) -> Option<String> {
+    if app.read_only {
+        return Some(format!(
+            "Error: Read-only mode. {} was not performed.",
+            action.describe()
+        ));
+    }
+    let (requirement, offenders) = app.policy_requirement(action);
+    (requirement == policy::Requirement::Forbidden).then(|| {
+        format!(
+            "Error: Policy forbids {} on {}.",
+            action.kind().label(),
+            offenders.join(", ")
+        )
+    })
+}
+
+/// Runs `action` without any further checks. Use `request_action` instead.
+fn execute_action(app: &mut App, action: PendingAction) {
+    match app.operations.submit(action.clone()) {
+        Some((id, cancel)) => run_operation(app, id, action, cancel),
+        None => {
+            app.state = AppState::Processing(format!(
+                "Queued: {}. It starts once the operation ahead of it finishes.",
+                action.describe()
+            ))
+        }
+    }
+}
+
+/// Starts a tracked operation. Its task ends by sending `OperationFinished(id)`; paths that
+/// fail before spawning finish it right away.
+fn run_operation(app: &mut App, id: u64, action: PendingAction, cancel: Cancel) {
+    match action {
+        PendingAction::Start(instance_id) => start_instance(app, id, instance_id, cancel),
+        PendingAction::Stop(instance_id) => stop_instance(app, id, instance_id, cancel),
+        PendingAction::Hibernate(instance_id) => hibernate_instance(app, id, instance_id, cancel),
+        PendingAction::Reboot { instance_id, force } => {
+            reboot_instance(app, id, instance_id, force, cancel)
+        }
+        PendingAction::Resize {
+            instance_id,
+            new_type,
+        } => match app.instances.iter().find(|i| i.id == instance_id).cloned() {
+            Some(instance) => start_resize(app, id, instance, new_type, cancel),
+            None => {
+                app.state =
+                    AppState::Processing(format!("Error: Instance {} not found.", instance_id));
+                finish_operation(app, id);
+            }
+        },
+        PendingAction::ToggleProtection { instance_id, stop } => {
+            toggle_protection(app, id, &instance_id, stop)
+        }
+        PendingAction::BulkStop(instance_ids) => stop_instances_bulk(app, id, instance_ids, cancel),
+    }
+}
+
+/// Cancels the newest operation on the selected instance.
+fn cancel_operation(app: &mut App) {
+    let Some(instance_id) = app
+        .list_state
+        .selected()
+        .and_then(|i| app.filtered_instances.get(i))
+        .map(|i| i.id.clone())
+    else {
+        return;
+    };
+    app.state = match app.operations.cancel_latest(&instance_id) {
+        Some(description) => AppState::Processing(format!(
+            "Cancelling: {}. Running operations stop before their next step.",
+            description
+        )),
+        None => AppState::Processing(format!("Error: No operation to cancel on {}.", instance_id)),
+    };
+}
+
+/// Forgets a finished operation and starts whatever was queued behind it.
+fn finish_operation(app: &mut App, id: u64) {
+    for (id, action, cancel) in app.operations.finish(id) {
+        run_operation(app, id, action, cancel);
+    }
+}
+
+fn start_instance(app: &mut App, id: u64, instance_id: String, cancel: Cancel) {
+    app.state = AppState::Processing(format!("Starting {}...", instance_id));
+    let tx = app.event_tx.clone();
+    let client = app.ec2_client.clone();
+    let targets: &[WaitFor] = if app.config.wait_for_status_checks.unwrap_or(false) {
+        &[WaitFor::Running, WaitFor::StatusOk]
+    } else {
+        &[WaitFor::Running]
+    };
+
+    tokio::spawn(async move {
+        if let Err(e) = client.start_instance(&instance_id).await {
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new("Start failed", &e)))
+                .await;
+        } else {
+            actions::await_completion(&client, &instance_id, targets, "Started.", &cancel, &tx)
+                .await;
+        }
+        let _ = tx.send(AppEvent::OperationFinished(id)).await;
+    });
+}
+
+fn stop_instance(app: &mut App, id: u64, instance_id: String, cancel: Cancel) {
+    app.state = AppState::Processing(format!("Stopping {}...", instance_id));
+    let tx = app.event_tx.clone();
+    let client = app.ec2_client.clone();
+    tokio::spawn(async move {
+        if let Err(e) = client.stop_instance(&instance_id).await {
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new("Stop failed", &e)))
+                .await;
+        } else {
+            actions::await_completion(
+                &client,
+                &instance_id,
+                &[WaitFor::Stopped],
+                "Stopped.",
+                &cancel,
+                &tx,
+            )
+            .await;
+        }
+        let _ = tx.send(AppEvent::OperationFinished(id)).await;
+    });
+}
+
+fn hibernate_instance(app: &mut App, id: u64, instance_id: String, cancel: Cancel) {
+    app.state = AppState::Processing(format!("Hibernating {}...", instance_id));
+    let tx = app.event_tx.clone();
+    let client = app.ec2_client.clone();
+    tokio::spawn(async move {
+        if let Err(e) = client.hibernate_instance(&instance_id).await {
+            let _ = tx
+                .send(AppEvent::Failed(FriendlyError::new("Hibernate failed", &e)))
+                .await;
+        } else {
+            actions::await_completion(
+                &client,
+                &instance_id,
+                &[WaitFor::Stopped],
+                "Hibernated.",
+                &cancel,
+                &tx,
+            )
+            .await;
+        }
+        let _ = tx.send(AppEvent::OperationFinished(id)).await;
+    });
+}
+
+/// Both kinds of reboot only count as done once the status checks pass again.
+fn reboot_instance(app: &mut App, id: u64, instance_id: String, force: bool, cancel: Cancel) {
+    let tx = app.event_tx.clone();
+    let client = app.ec2_client.clone();
+    if force {
+        app.state = AppState::Processing(format!("Forced Rebooting {}...", instance_id));
+        tokio::spawn(async move {
+            if let Err(e) = client.force_reboot_instance(&instance_id, &cancel).await {
+                let _ = tx
+                    .send(AppEvent::Failed(FriendlyError::new(
+                        "Forced Reboot failed",
+                        &e,
+                    )))
+                    .await;
+            } else {
+                actions::await_completion(
+                    &client,
+                    &instance_id,
+                    &[WaitFor::StatusOk],
+                    "Forced Reboot Complete.",
+                    &cancel,
+                    &tx,
+                )
+                .await;
+            }
+            let _ = tx.send(AppEvent::OperationFinished(id)).await;
+        });
+    } else {
+        app.state = AppState::Processing(format!("Rebooting {}...", instance_id));
+        tokio::spawn(async move {
+            if let Err(e) = client.reboot_instance(&instance_id).await {
+                let _ = tx
+                    .send(AppEvent::Failed(FriendlyError::new("Reboot failed", &e)))
+                    .await;
+            } else {
+                actions::await_completion(
+                    &client,
+                    &instance_id,
+                    &[WaitFor::StatusOk],
+                    "Rebooted (the AWS runtime does not reset).",
+                    &cancel,
+                    &tx,
+                )
+                .await;
+            }
+            let _ = tx.send(AppEvent::OperationFinished(id)).await;
+        });
+    }
+}
+
+/// Runs the resize pipeline in the background, reporting each step through `AppEvent`s.
+fn start_resize(app: &mut App, id: u64, instance: InstanceInfo, new_type: String, cancel: Cancel) {
+    let spot_persistent = app
+        .spot_requests
+        .get(&instance.id)
+        .map(|r| r.is_persistent())
+        .unwrap_or(false);
+    if instance.is_spot() && !spot_persistent {
+        app.state = AppState::Processing(format!(
+            "Error: {} is backed by a one-time (or unknown) spot request. \
+             Only instances from persistent spot requests can be stopped, \
+             so they cannot be resized in place.",
+            instance.id
+        ));
+        finish_operation(app, id);
+    } else {
+        app.state = AppState::Processing(format!("Stopping {}...", instance.id));
+
+        let client = app.ec2_client.clone();
+        let tx = app.event_tx.clone();
+        let instance_id = instance.id.clone();
+        let credit_spec = app
+            .config
+            .t_family_credit
+            .clone()
+            .unwrap_or("standard".to_string());
+        let checks = app.config.wait_for_status_checks.unwrap_or(false);
+
+        tokio::spawn(async move {
+            if actions::resize_instance(
+                &client,
+                &instance_id,
+                &new_type,
+                &credit_spec,
+                true,
+                &cancel,
+                &tx,
+            )
+            .await
+            {
+                // The pipeline itself already waited for running
+                let targets: &[WaitFor] = if checks { &[WaitFor::StatusOk] } else { &[] };
+                actions::await_completion(&client, &instance_id, targets, "Done.", &cancel, &tx)
+                    .await;
+            }
+            let _ = tx.send(AppEvent::OperationFinished(id)).await;
+        });
+    }
+}
+
+/// Stops every instance in `instance_ids`, reporting failures together once all have been tried.
+fn stop_instances_bulk(app: &mut App, id: u64, instance_ids: Vec<String>, cancel: Cancel) {
+    let (protected, instance_ids): (Vec<String>, Vec<String>) = instance_ids
+        .into_iter()
+        .partition(|id| app.stop_protected(id));
+    if instance_ids.is_empty() {
+        app.state = AppState::Processing(format!(
+            "Error: All selected instances have stop protection enabled: {}",
+            protected.join(", ")
+        ));
+        finish_operation(app, id);
+        return;
+    }
+
+    app.state = AppState::Processing(format!("Stopping {} instances...", instance_ids.len()));
+    let tx = app.event_tx.clone();
+    let client = app.ec2_client.clone();
+    tokio::spawn(async move {
+        let mut failures: Vec<String> = protected
+            .iter()
+            .map(|id| format!("{}: stop protection enabled", id))
+            .collect();
+        let mut requested = Vec::new();
+        for instance_id in &instance_ids {
+            // Cancelling skips the instances not stopped yet
+            if cancel.is_cancelled() {
+                failures.push(format!("{}: cancelled", instance_id));
+                continue;
+            }
+            match client.stop_instance(instance_id).await {
+                Ok(()) => requested.push(instance_id),
+                Err(e) => failures.push(format!(
+                    "{}: {}",
+                    instance_id,
+                    FriendlyError::new("Stop failed", &e).summary
+                )),
+            }
+        }
+        // The instances stop in parallel, so waiting on each in turn costs little
+        for instance_id in requested {
+            let _ = tx
+                .send(AppEvent::Progress(
+                    instance_id.clone(),
+                    format!("Waiting for {} to stop...", instance_id),
+                ))
+                .await;
+            if let Err(e) = client
+                .wait_for(instance_id, WaitFor::Stopped, &cancel)
+                .await
+            {
+                failures.push(format!("{}: {}", instance_id, e));
+            }
+        }
+
+        if failures.is_empty() {
+            let _ = tx
+                .send(AppEvent::Message(format!(
+                    "Stopped {} instances. Refreshing...",
+                    instance_ids.len()
+                )))
+                .await;
+            let _ = tx.send(AppEvent::InstancesUpdated).await;
+        } else {
+            // Refresh first so the error stays on screen
+            let _ = tx.send(AppEvent::InstancesUpdated).await;
+            let _ = tx
+                .send(AppEvent::Error(format!(
+                    "Stop failed for {} of {} instances: {}",
+                    failures.len(),
+                    instance_ids.len() + protected.len(),
+                    failures.join("; ")
+                )))
+                .await;
+        }
+        let _ = tx.send(AppEvent::OperationFinished(id)).await;
+    });
+}
+
+/// Flips stop protection (`stop`) or termination protection of `instance_id`.
+fn toggle_protection(app: &mut App, id: u64, instance_id: &str, stop: bool) {
+    let Some(Some(current)) = app.protection.get(instance_id).copied() else {
+        app.state = AppState::Processing(format!(
+            "Error: Protection flags for {} are still loading.",
+            instance_id
+        ));
+        finish_operation(app, id);
+        return;
+    };
+    let (label, enable) = if stop {
+        ("Stop protection", !current.stop)
+    } else {
+        ("Termination protection", !current.termination)
+    };
+    let verb = if enable { "Enabling" } else { "Disabling" };
+    app.state = AppState::Processing(format!(
+        "{} {} for {}...",
+        verb,
+        label.to_lowercase(),
+        instance_id
+    ));
+
+    let client = app.ec2_client.clone();
+    let tx = app.event_tx.clone();
+    let instance_id = instance_id.to_string();
+    tokio::spawn(async move {
+        let result = if stop {
+            client.set_stop_protection(&instance_id, enable).await
+        } else {
+            client
+                .set_termination_protection(&instance_id, enable)
+                .await
+        };
+        match result {
+            Ok(()) => {
+                let mut updated = current;
+                if stop {
+                    updated.stop = enable;
+                } else {
+                    updated.termination = enable;
+                }
+                let _ = tx
+                    .send(AppEvent::ProtectionFetched(instance_id.clone(), updated))
+                    .await;
+                let _ = tx
+                    .send(AppEvent::Message(format!(
+                        "{} {} for {}.",
+                        label,
+                        if enable { "enabled" } else { "disabled" },
+                        instance_id
+                    )))
+                    .await;
+            }
+            Err(e) => {
+                let _ = tx
+                    .send(AppEvent::Failed(FriendlyError::new(
+                        &format!("{} change failed", label),
+                        &e,
+                    )))
+                    .await;
+            }
+        }
+        let _ = tx.send(AppEvent::OperationFinished(id)).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec2::Ec2Client;
+    use crate::errors::ErrorKind;
+    use crate::fake::{self, FakeFleet};
+    use std::sync::Arc;
+
+    fn fleet_app(fleet: &Arc<FakeFleet>, instances: Vec<InstanceInfo>) -> App {
+        App::for_test(Ec2Client::from_backend(fleet.clone()), instances)
+    }
+
+    fn resize(instance_id: &str, new_type: &str) -> PendingAction {
+        PendingAction::Resize {
+            instance_id: instance_id.to_string(),
+            new_type: new_type.to_string(),
+        }
+    }
+
+    /// Feeds background events through `handle_event` until none arrive for ten (simulated)
+    /// minutes, returning every popup message shown along the way.
+    async fn drain(app: &mut App) -> Vec<String> {
+        let mut messages = Vec::new();
+        loop {
+            if let AppState::Processing(msg) = &app.state
+                && messages.last() != Some(msg)
+            {
+                messages.push(msg.clone());
+            }
+            match tokio::time::timeout(Duration::from_secs(600), app.event_rx.recv()).await {
+                Ok(Some(event)) => handle_event(app, event),
+                _ => break,
+            }
+        }
+        messages
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resize_flow() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_delays(Duration::from_secs(10), Duration::from_secs(30)),
+        );
+        let mut app = fleet_app(&fleet, vec![instance.clone()]);
+
+        execute_action(&mut app, resize("i-1", "m5.xlarge"));
+        let messages = drain(&mut app).await;
+
+        assert_eq!(
+            messages,
+            [
+                "Stopping i-1...",
+                "Waiting for stop...",
+                "Changing to m5.xlarge...",
+                "Starting...",
+                "Waiting for running...",
+                "Done. Refreshing...",
+            ]
+        );
+        assert!(matches!(app.state, AppState::List));
+        // The refresh only runs once the instance is back up
+        assert_eq!(app.instances[0].instance_type, "m5.xlarge");
+        assert_eq!(app.instances[0].state, "running");
+        assert_eq!(fleet.instance("i-1").unwrap().state, "running");
+        assert!(app.operations.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resize_waits_for_status_checks() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_delays(Duration::from_secs(10), Duration::from_secs(30))
+                .with_check_delay(Duration::from_secs(120)),
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+        app.config.wait_for_status_checks = Some(true);
+
+        execute_action(&mut app, resize("i-1", "m5.xlarge"));
+        let messages = drain(&mut app).await;
+
+        assert_eq!(
+            messages[messages.len() - 3..],
+            [
+                "Waiting for running...",
+                "Waiting for status checks OK...",
+                "Done. Refreshing...",
+            ]
+        );
+        assert!(matches!(app.state, AppState::List));
+        assert!(app.status_checks["i-1"].ok());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_start_reports_impaired_instance() {
+        let instance = fake::instance("i-1", "m5.large", "stopped");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_delays(Duration::from_secs(10), Duration::from_secs(30))
+                .with_check_delay(Duration::from_secs(60))
+                .with_impaired("i-1"),
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+        app.config.wait_for_status_checks = Some(true);
+
+        execute_action(&mut app, PendingAction::Start("i-1".to_string()));
+        drain(&mut app).await;
+
+        match &app.state {
+            AppState::Processing(msg) => assert_eq!(
+                msg,
+                "Error: Status checks failed: i-1 is impaired (instance check failed)"
+            ),
+            other => panic!("expected the impaired report, got {:?}", other),
+        }
+        assert_eq!(app.status_checks["i-1"].instance, "impaired");
+        assert!(app.operations.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_resize_reports_modify_failure() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(FakeFleet::new().with_instance(instance.clone()));
+        fleet.fail(
+            "ModifyInstanceAttribute",
+            "UnauthorizedOperation",
+            "You are not authorized to perform this operation.",
+        );
+        let mut app = fleet_app(&fleet, vec![instance.clone()]);
+
+        execute_action(&mut app, resize("i-1", "m5.xlarge"));
+        drain(&mut app).await;
+
+        match &app.state {
+            AppState::Processing(msg) => assert!(msg.starts_with("Error: Modify failed")),
+            other => panic!("expected an error popup, got {:?}", other),
+        }
+        assert!(app.error_detail.is_some());
+        let after = fleet.instance("i-1").unwrap();
+        assert_eq!(after.instance_type, "m5.large");
+        assert_eq!(after.state, "stopped");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_force_reboot_flow() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_delays(Duration::from_secs(10), Duration::from_secs(20)),
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+
+        execute_action(
+            &mut app,
+            PendingAction::Reboot {
+                instance_id: "i-1".to_string(),
+                force: true,
+            },
+        );
+        let messages = drain(&mut app).await;
+
+        assert_eq!(
+            messages,
+            [
+                "Forced Rebooting i-1...",
+                "Waiting for status checks OK...",
+                "Forced Reboot Complete. Refreshing...",
+            ]
+        );
+        // The start is only sent once the forced stop has settled
+        let calls = fleet.calls();
+        let stop = calls.iter().position(|c| c == "StopInstances i-1").unwrap();
+        let start = calls
+            .iter()
+            .position(|c| c == "StartInstances i-1")
+            .unwrap();
+        assert!(stop < start);
+        assert!(calls[stop..start].contains(&"DescribeInstances i-1".to_string()));
+        assert_eq!(fleet.instance("i-1").unwrap().state, "running");
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_conflicting_operations_run_one_after_another() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_delays(Duration::from_secs(10), Duration::from_secs(20)),
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+
+        execute_action(&mut app, resize("i-1", "m5.xlarge"));
+        execute_action(&mut app, PendingAction::Stop("i-1".to_string()));
+        assert_eq!(app.operations.all().len(), 2);
+        assert_eq!(app.operations.all()[1].status, operations::Status::Queued);
+        drain(&mut app).await;
+
+        // The stop only went out after the resize had started the instance again
+        let calls = fleet.calls();
+        let start = calls
+            .iter()
+            .position(|c| c == "StartInstances i-1")
+            .unwrap();
+        let last_stop = calls
+            .iter()
+            .rposition(|c| c == "StopInstances i-1")
+            .unwrap();
+        assert!(start < last_stop);
+        assert_eq!(fleet.instance("i-1").unwrap().instance_type, "m5.xlarge");
+        assert!(app.operations.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_cancel_resize_while_waiting_for_stop() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_delays(Duration::from_secs(10), Duration::from_secs(60)),
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+        app.list_state.select(Some(0));
+
+        execute_action(&mut app, resize("i-1", "m5.xlarge"));
+        while !matches!(&app.state, AppState::Processing(msg) if msg == "Waiting for stop...") {
+            let event = app.event_rx.recv().await.unwrap();
+            handle_event(&mut app, event);
+        }
+        cancel_operation(&mut app);
+        drain(&mut app).await;
+
+        match &app.state {
+            AppState::Processing(msg) => assert_eq!(
+                msg,
+                "Error: Resize cancelled; i-1 is stopping (or stopped) with its old type."
+            ),
+            other => panic!("expected the cancellation, got {:?}", other),
+        }
+        assert!(
+            !fleet
+                .calls()
+                .contains(&"ModifyInstanceAttribute i-1".to_string())
+        );
+        assert_eq!(fleet.instance("i-1").unwrap().instance_type, "m5.large");
+        assert!(app.operations.is_empty());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_refresh_flow() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(
+            FakeFleet::new()
+                .with_instance(instance.clone())
+                .with_instance(fake::instance("i-2", "t3.micro", "stopped")),
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+        app.ec2_client.stop_instance("i-1").await.unwrap();
+
+        spawn_refresh(&app);
+        drain(&mut app).await;
+
+        let states: Vec<(&str, &str)> = app
+            .instances
+            .iter()
+            .map(|i| (i.id.as_str(), i.state.as_str()))
+            .collect();
+        assert_eq!(states, [("i-1", "stopped"), ("i-2", "stopped")]);
+        assert_eq!(app.filtered_instances.len(), 2);
+        assert!(app.last_refreshed.is_some());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_refresh_with_expired_credentials_only_raises_banner() {
+        let instance = fake::instance("i-1", "m5.large", "running");
+        let fleet = Arc::new(FakeFleet::new().with_instance(instance.clone()));
+        fleet.fail(
+            "DescribeInstances",
+            "ExpiredToken",
+            "The security token included in the request is expired",
+        );
+        let mut app = fleet_app(&fleet, vec![instance]);
+
+        spawn_refresh(&app);
+        drain(&mut app).await;
+
+        assert_eq!(app.credentials_problem, Some(ErrorKind::ExpiredCredentials));
+        assert!(matches!(app.state, AppState::List));
+    }
+}
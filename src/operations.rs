@@ -0,0 +1,199 @@
+use crate::app::PendingAction;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::Notify;
+
+/// Asks a running operation to stop at its next step boundary.
+#[derive(Clone, Default)]
+pub struct Cancel(Arc<CancelState>);
+
+#[derive(Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl Cancel {
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// Resolves once `cancel` has been called, for racing against a wait.
+    pub async fn cancelled(&self) {
+        loop {
+            // Created before the check so a cancel in between still wakes us
+            let notified = self.0.notify.notified();
+            if self.is_cancelled() {
+                return;
+            }
+            notified.await;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Status {
+    Queued,
+    Running,
+    Cancelling,
+}
+
+pub struct Operation {
+    pub id: u64,
+    pub action: PendingAction,
+    pub step: String,
+    pub status: Status,
+    cancel: Cancel,
+}
+
+impl Operation {
+    fn conflicts_with(&self, action: &PendingAction) -> bool {
+        let ids = self.action.instance_ids();
+        action.instance_ids().iter().any(|id| ids.contains(id))
+    }
+}
+
+/// Every in-flight and queued operation, oldest first. An operation only runs once nothing
+/// ahead of it touches the same instance.
+#[derive(Default)]
+pub struct Operations {
+    ops: Vec<Operation>,
+    next_id: u64,
+}
+
+impl Operations {
+    pub fn all(&self) -> &[Operation] {
+        &self.ops
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.ops.is_empty()
+    }
+
+    /// Tracks `action`. Returns its ID and cancel handle if it can start right away, or
+    /// `None` if it has been queued behind a conflicting operation.
+    pub fn submit(&mut self, action: PendingAction) -> Option<(u64, Cancel)> {
+        self.next_id += 1;
+        let busy = self.ops.iter().any(|op| op.conflicts_with(&action));
+        let op = Operation {
+            id: self.next_id,
+            action,
+            step: if busy { "Queued" } else { "Starting" }.to_string(),
+            status: if busy {
+                Status::Queued
+            } else {
+                Status::Running
+            },
+            cancel: Cancel::default(),
+        };
+        let started = (!busy).then(|| (op.id, op.cancel.clone()));
+        self.ops.push(op);
+        started
+    }
+
+    /// Records the step the running operation on `instance_id` has reached.
+    pub fn set_step(&mut self, instance_id: &str, step: &str) {
+        if let Some(op) = self.ops.iter_mut().find(|op| {
+            op.status != Status::Queued && op.action.instance_ids().contains(&instance_id)
+        }) {
+            op.step = step.to_string();
+        }
+    }
+
+    /// Drops a finished operation and returns the queued ones that can start now, already
+    /// marked as running.
+    pub fn finish(&mut self, id: u64) -> Vec<(u64, PendingAction, Cancel)> {
+        self.ops.retain(|op| op.id != id);
+
+        let mut started = Vec::new();
+        for i in 0..self.ops.len() {
+            let (ahead, rest) = self.ops.split_at_mut(i);
+            let op = &mut rest[0];
+            if op.status == Status::Queued
+                && !ahead.iter().any(|other| other.conflicts_with(&op.action))
+            {
+                op.status = Status::Running;
+                op.step = "Starting".to_string();
+                started.push((op.id, op.action.clone(), op.cancel.clone()));
+            }
+        }
+        started
+    }
+
+    /// Cancels the newest operation on `instance_id`: a queued one is dropped, a running one
+    /// stops before its next step. Returns what was cancelled.
+    pub fn cancel_latest(&mut self, instance_id: &str) -> Option<String> {
+        let index = self.ops.iter().rposition(|op| {
+            op.status != Status::Cancelling && op.action.instance_ids().contains(&instance_id)
+        })?;
+        let op = &mut self.ops[index];
+        let description = op.action.describe();
+        if op.status == Status::Queued {
+            self.ops.remove(index);
+        } else {
+            op.status = Status::Cancelling;
+            op.cancel.cancel();
+        }
+        Some(description)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(id: &str) -> PendingAction {
+        PendingAction::Stop(id.to_string())
+    }
+
+    #[test]
+    fn test_conflicting_operations_queue_in_order() {
+        let mut ops = Operations::default();
+        let (first, _) = ops.submit(stop("i-1")).unwrap();
+        assert!(
+            ops.submit(PendingAction::Start("i-1".to_string()))
+                .is_none()
+        );
+        assert!(ops.submit(stop("i-2")).is_some());
+        assert!(ops.submit(stop("i-1")).is_none());
+
+        let started = ops.finish(first);
+        assert_eq!(started.len(), 1);
+        assert_eq!(started[0].1, PendingAction::Start("i-1".to_string()));
+        // The second i-1 operation still waits for the start
+        assert_eq!(ops.all()[2].status, Status::Queued);
+    }
+
+    #[test]
+    fn test_cancel_drops_queued_before_signalling_running() {
+        let mut ops = Operations::default();
+        let (_, cancel) = ops.submit(stop("i-1")).unwrap();
+        ops.submit(PendingAction::Start("i-1".to_string()));
+
+        assert_eq!(ops.cancel_latest("i-1").as_deref(), Some("Start i-1"));
+        assert_eq!(ops.all().len(), 1);
+        assert!(!cancel.is_cancelled());
+
+        assert_eq!(ops.cancel_latest("i-1").as_deref(), Some("Stop i-1"));
+        assert!(cancel.is_cancelled());
+        assert_eq!(ops.all()[0].status, Status::Cancelling);
+        assert!(ops.cancel_latest("i-1").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_wakes_waiters() {
+        let cancel = Cancel::default();
+        let waiter = tokio::spawn({
+            let cancel = cancel.clone();
+            async move { cancel.cancelled().await }
+        });
+        tokio::task::yield_now().await;
+        cancel.cancel();
+        waiter.await.unwrap();
+    }
+}
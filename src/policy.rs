@@ -0,0 +1,143 @@
+use crate::config::{PolicyAction, PolicyRule};
+use crate::ec2::InstanceInfo;
+
+/// What an action needs before it may run, from least to most strict.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Requirement {
+    None,
+    Confirm,
+    TypeName,
+    Forbidden,
+}
+
+impl Requirement {
+    pub fn label(self) -> &'static str {
+        match self {
+            Requirement::None => "no confirmation",
+            Requirement::Confirm => "confirmation",
+            Requirement::TypeName => "the instance name typed",
+            Requirement::Forbidden => "forbidden",
+        }
+    }
+}
+
+impl PolicyAction {
+    pub fn label(self) -> &'static str {
+        match self {
+            PolicyAction::Start => "start",
+            PolicyAction::Stop => "stop",
+            PolicyAction::Hibernate => "hibernate",
+            PolicyAction::Reboot => "reboot",
+            PolicyAction::Resize => "resize",
+            PolicyAction::Protection => "change protection",
+        }
+    }
+}
+
+fn listed(actions: &Option<Vec<PolicyAction>>, action: PolicyAction) -> bool {
+    actions.as_ref().is_some_and(|a| a.contains(&action))
+}
+
+/// The strictest requirement for `action` among the rules that select `instance`.
+pub fn requirement(
+    rules: &[PolicyRule],
+    instance: &InstanceInfo,
+    action: PolicyAction,
+) -> Requirement {
+    rules
+        .iter()
+        .filter(|r| r.select.matches(instance))
+        .map(|r| {
+            if listed(&r.forbid, action) {
+                Requirement::Forbidden
+            } else if listed(&r.type_name, action) {
+                Requirement::TypeName
+            } else if listed(&r.confirm, action) {
+                Requirement::Confirm
+            } else {
+                Requirement::None
+            }
+        })
+        .max()
+        .unwrap_or(Requirement::None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::InstanceSelector;
+    use std::collections::HashMap;
+
+    fn instance(id: &str, env: &str) -> InstanceInfo {
+        InstanceInfo {
+            id: id.to_string(),
+            name: Some(format!("{}-db", env)),
+            instance_type: "m5.large".to_string(),
+            state: "running".to_string(),
+            architecture: Some("x86_64".to_string()),
+            availability_zone: None,
+            public_ip: None,
+            launch_time: None,
+            instance_lifecycle: None,
+            spot_instance_request_id: None,
+            tags: HashMap::from([("env".to_string(), env.to_string())]),
+            hibernation_configured: false,
+            state_reason: None,
+        }
+    }
+
+    fn rule(
+        tag: &str,
+        confirm: &[PolicyAction],
+        type_name: &[PolicyAction],
+        forbid: &[PolicyAction],
+    ) -> PolicyRule {
+        PolicyRule {
+            name: None,
+            select: InstanceSelector {
+                tag: Some(tag.to_string()),
+                ..Default::default()
+            },
+            confirm: Some(confirm.to_vec()),
+            type_name: Some(type_name.to_vec()),
+            forbid: Some(forbid.to_vec()),
+        }
+    }
+
+    #[test]
+    fn test_unmatched_instance_needs_nothing() {
+        let rules = vec![rule("env=prod", &[PolicyAction::Start], &[], &[])];
+
+        assert_eq!(
+            requirement(&rules, &instance("i-1", "dev"), PolicyAction::Start),
+            Requirement::None
+        );
+        assert_eq!(
+            requirement(&rules, &instance("i-2", "prod"), PolicyAction::Start),
+            Requirement::Confirm
+        );
+        assert_eq!(
+            requirement(&rules, &instance("i-2", "prod"), PolicyAction::Stop),
+            Requirement::None
+        );
+    }
+
+    #[test]
+    fn test_strictest_rule_wins() {
+        let rules = vec![
+            rule("env=prod", &[PolicyAction::Stop], &[], &[]),
+            rule("env", &[], &[PolicyAction::Stop], &[]),
+            rule("env=prod", &[], &[], &[PolicyAction::Hibernate]),
+        ];
+        let prod = instance("i-1", "prod");
+
+        assert_eq!(
+            requirement(&rules, &prod, PolicyAction::Stop),
+            Requirement::TypeName
+        );
+        assert_eq!(
+            requirement(&rules, &prod, PolicyAction::Hibernate),
+            Requirement::Forbidden
+        );
+    }
+}
@@ -1,16 +1,55 @@
+use crate::recording::Traffic;
 use anyhow::Result;
+use async_trait::async_trait;
 use aws_config::meta::region::RegionProviderChain;
 use aws_sdk_pricing::Client;
 use serde_json::Value;
+use std::sync::Arc;
+
+/// Source of on-demand prices, so tests can avoid the Price List API.
+#[async_trait]
+pub trait PricingBackend: Send + Sync {
+    async fn fetch_all_on_demand_prices(&self) -> Result<std::collections::HashMap<String, f64>>;
+}
 
 #[derive(Clone)]
 pub struct PricingClient {
+    backend: Arc<dyn PricingBackend>,
+}
+
+impl PricingClient {
+    pub async fn new(
+        region: &str,
+        endpoint_url: Option<&str>,
+        traffic: Option<&Traffic>,
+    ) -> Result<Self> {
+        Ok(Self::from_backend(Arc::new(
+            SdkPricing::new(region, endpoint_url, traffic).await?,
+        )))
+    }
+
+    pub fn from_backend(backend: Arc<dyn PricingBackend>) -> Self {
+        Self { backend }
+    }
+
+    pub async fn fetch_all_on_demand_prices(
+        &self,
+    ) -> Result<std::collections::HashMap<String, f64>> {
+        self.backend.fetch_all_on_demand_prices().await
+    }
+}
+
+pub struct SdkPricing {
     client: Client,
     region: String,
 }
 
-impl PricingClient {
-    pub async fn new(region: &str) -> Result<Self> {
+impl SdkPricing {
+    pub async fn new(
+        region: &str,
+        endpoint_url: Option<&str>,
+        traffic: Option<&Traffic>,
+    ) -> Result<Self> {
         // Pricing API is generally available in us-east-1 and ap-south-1.
         // We almost always want to query us-east-1 for the global price list.
         // However, we need to filter by the target "Location" (which is human readable region name).
@@ -31,17 +70,28 @@ impl PricingClient {
             .load()
             .await;
 
-        let client = Client::new(&pricing_config);
+        let mut builder = aws_sdk_pricing::config::Builder::from(&pricing_config);
+        builder.set_endpoint_url(endpoint_url.map(str::to_string));
+        if let Some(traffic) = traffic {
+            builder.set_http_client(Some(
+                traffic.http_client("pricing", pricing_config.http_client())?,
+            ));
+            if let Some(credentials) = traffic.credentials() {
+                builder.set_credentials_provider(Some(credentials));
+            }
+        }
+        let client = Client::from_conf(builder.build());
 
         Ok(Self {
             client,
             region: region.to_string(),
         })
     }
+}
 
-    pub async fn fetch_all_on_demand_prices(
-        &self,
-    ) -> Result<std::collections::HashMap<String, f64>> {
+#[async_trait]
+impl PricingBackend for SdkPricing {
+    async fn fetch_all_on_demand_prices(&self) -> Result<std::collections::HashMap<String, f64>> {
         let mut prices = std::collections::HashMap::new();
         let mut next_token = None;
 
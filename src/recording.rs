@@ -0,0 +1,393 @@
+use anyhow::{Context, Result};
+use aws_sdk_ec2::config::{Credentials, SharedCredentialsProvider};
+use aws_smithy_runtime_api::client::http::{
+    HttpClient, HttpConnector, HttpConnectorFuture, HttpConnectorSettings, SharedHttpClient,
+    SharedHttpConnector,
+};
+use aws_smithy_runtime_api::client::orchestrator::{HttpRequest, HttpResponse};
+use aws_smithy_runtime_api::client::result::ConnectorError;
+use aws_smithy_runtime_api::client::runtime_components::RuntimeComponents;
+use aws_smithy_runtime_api::http::StatusCode;
+use aws_smithy_types::body::SdkBody;
+use aws_smithy_types::byte_stream::ByteStream;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// What identifies a request on replay. The host is left out so a session recorded against
+/// one endpoint replays against any other.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RequestKey {
+    pub service: String,
+    pub method: String,
+    pub path: String,
+    pub target: Option<String>, // X-Amz-Target header of JSON protocols such as Pricing
+    pub body: String,
+}
+
+impl RequestKey {
+    fn of(service: &str, request: &HttpRequest) -> Self {
+        let uri = request.uri();
+        let path = match uri.find("://") {
+            Some(scheme_end) => match uri[scheme_end + 3..].find('/') {
+                Some(host_end) => uri[scheme_end + 3 + host_end..].to_string(),
+                None => "/".to_string(),
+            },
+            None => uri.to_string(),
+        };
+        Self {
+            service: service.to_string(),
+            method: request.method().to_string(),
+            path,
+            target: request.headers().get("x-amz-target").map(str::to_string),
+            body: request
+                .body()
+                .bytes()
+                .map(|b| String::from_utf8_lossy(b).into_owned())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// One request and the response it got; a session file holds one per line.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exchange {
+    pub request: RequestKey,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: String,
+}
+
+impl Exchange {
+    fn response(&self) -> Result<HttpResponse, ConnectorError> {
+        let status =
+            StatusCode::try_from(self.status).map_err(|e| ConnectorError::other(e.into(), None))?;
+        let mut response = HttpResponse::new(status, SdkBody::from(self.body.clone()));
+        for (name, value) in &self.headers {
+            response.headers_mut().append(name.clone(), value.clone());
+        }
+        Ok(response)
+    }
+}
+
+/// Recorded responses by request, served in the order they were recorded. The last
+/// response for a request keeps being served, so periodic refreshes don't run dry.
+#[derive(Debug, Default)]
+pub struct Tape {
+    exchanges: Mutex<HashMap<RequestKey, VecDeque<Exchange>>>,
+}
+
+impl Tape {
+    pub fn parse(content: &str) -> Result<Self> {
+        let mut exchanges: HashMap<RequestKey, VecDeque<Exchange>> = HashMap::new();
+        for (n, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let exchange: Exchange = serde_json::from_str(line)
+                .with_context(|| format!("Invalid recording at line {}", n + 1))?;
+            exchanges
+                .entry(exchange.request.clone())
+                .or_default()
+                .push_back(exchange);
+        }
+        Ok(Self {
+            exchanges: Mutex::new(exchanges),
+        })
+    }
+
+    fn next(&self, request: &RequestKey) -> Option<Exchange> {
+        let mut exchanges = self.exchanges.lock().unwrap();
+        let queue = exchanges.get_mut(request)?;
+        if queue.len() > 1 {
+            queue.pop_front()
+        } else {
+            queue.front().cloned()
+        }
+    }
+}
+
+/// Where the EC2 and Pricing clients' HTTP traffic goes besides the network.
+#[derive(Debug, Clone)]
+pub enum Traffic {
+    /// Requests go to AWS and every exchange is appended to the session file.
+    Record(Arc<Mutex<std::fs::File>>),
+    /// Requests are answered from a recorded session without touching the network.
+    Replay(Arc<Tape>),
+}
+
+impl Traffic {
+    /// Starts a new session file, replacing any previous one at `path`.
+    pub fn record(path: &Path) -> Result<Self> {
+        let file = std::fs::File::create(path)
+            .with_context(|| format!("Cannot create {}", path.display()))?;
+        Ok(Traffic::Record(Arc::new(Mutex::new(file))))
+    }
+
+    pub fn replay(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Cannot read {}", path.display()))?;
+        Ok(Traffic::Replay(Arc::new(Tape::parse(&content)?)))
+    }
+
+    pub fn is_replay(&self) -> bool {
+        matches!(self, Traffic::Replay(_))
+    }
+
+    /// The HTTP client for `service`. Recording wraps `live`, the client the SDK would
+    /// otherwise use.
+    pub fn http_client(
+        &self,
+        service: &'static str,
+        live: Option<SharedHttpClient>,
+    ) -> Result<SharedHttpClient> {
+        Ok(match self {
+            Traffic::Record(file) => SharedHttpClient::new(Recorder {
+                service,
+                live: live.context("No HTTP client available to record")?,
+                file: file.clone(),
+            }),
+            Traffic::Replay(tape) => SharedHttpClient::new(Replayer {
+                service,
+                tape: tape.clone(),
+            }),
+        })
+    }
+
+    /// Replayed requests are still signed, but never checked, so no real credentials are
+    /// needed to replay a session.
+    pub fn credentials(&self) -> Option<SharedCredentialsProvider> {
+        self.is_replay().then(|| {
+            SharedCredentialsProvider::new(Credentials::new(
+                "replay", "replay", None, None, "replay",
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Recorder {
+    service: &'static str,
+    live: SharedHttpClient,
+    file: Arc<Mutex<std::fs::File>>,
+}
+
+impl HttpClient for Recorder {
+    fn http_connector(
+        &self,
+        settings: &HttpConnectorSettings,
+        components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(RecordingConnector {
+            service: self.service,
+            live: self.live.http_connector(settings, components),
+            file: self.file.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct RecordingConnector {
+    service: &'static str,
+    live: SharedHttpConnector,
+    file: Arc<Mutex<std::fs::File>>,
+}
+
+impl HttpConnector for RecordingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let key = RequestKey::of(self.service, &request);
+        let response = self.live.call(request);
+        let file = self.file.clone();
+        HttpConnectorFuture::new(async move {
+            let response = response.await?;
+            let status = response.status().as_u16();
+            let headers = response
+                .headers()
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect();
+            // The body is buffered so it can be both saved and handed on to the SDK
+            let body = ByteStream::new(response.into_body())
+                .collect()
+                .await
+                .map_err(|e| ConnectorError::io(e.into()))?
+                .into_bytes();
+            let exchange = Exchange {
+                request: key,
+                status,
+                headers,
+                body: String::from_utf8_lossy(&body).into_owned(),
+            };
+            // A failed write must not fail the call it records
+            if let Ok(mut line) = serde_json::to_string(&exchange) {
+                line.push('\n');
+                let _ = file.lock().unwrap().write_all(line.as_bytes());
+            }
+            exchange.response()
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Replayer {
+    service: &'static str,
+    tape: Arc<Tape>,
+}
+
+impl HttpClient for Replayer {
+    fn http_connector(
+        &self,
+        _settings: &HttpConnectorSettings,
+        _components: &RuntimeComponents,
+    ) -> SharedHttpConnector {
+        SharedHttpConnector::new(ReplayingConnector {
+            service: self.service,
+            tape: self.tape.clone(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct ReplayingConnector {
+    service: &'static str,
+    tape: Arc<Tape>,
+}
+
+impl HttpConnector for ReplayingConnector {
+    fn call(&self, request: HttpRequest) -> HttpConnectorFuture {
+        let key = RequestKey::of(self.service, &request);
+        HttpConnectorFuture::ready(match self.tape.next(&key) {
+            Some(exchange) => exchange.response(),
+            None => Err(ConnectorError::other(
+                format!(
+                    "No recorded response for {} {} {}",
+                    key.service, key.method, key.body
+                )
+                .into(),
+                None,
+            )),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec2::{Ec2Backend, SdkEc2};
+    use aws_sdk_ec2::config::{BehaviorVersion, Region};
+
+    const DESCRIBE_INSTANCES: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<DescribeInstancesResponse xmlns="http://ec2.amazonaws.com/doc/2016-11-15/">
+    <requestId>8f7724cf-496f-496e-8fe3-example</requestId>
+    <reservationSet>
+        <item>
+            <reservationId>r-1234567890abcdef0</reservationId>
+            <instancesSet>
+                <item>
+                    <instanceId>i-1234567890abcdef0</instanceId>
+                    <instanceType>m5.large</instanceType>
+                    <instanceState><code>16</code><name>running</name></instanceState>
+                </item>
+            </instancesSet>
+        </item>
+    </reservationSet>
+</DescribeInstancesResponse>"#;
+
+    /// Answers every request with the same body, standing in for AWS.
+    #[derive(Debug, Clone)]
+    struct Canned(&'static str);
+
+    impl HttpConnector for Canned {
+        fn call(&self, _request: HttpRequest) -> HttpConnectorFuture {
+            let mut response =
+                HttpResponse::new(StatusCode::try_from(200).unwrap(), SdkBody::from(self.0));
+            response
+                .headers_mut()
+                .insert("content-type", "text/xml;charset=UTF-8");
+            HttpConnectorFuture::ready(Ok(response))
+        }
+    }
+
+    impl HttpClient for Canned {
+        fn http_connector(
+            &self,
+            _settings: &HttpConnectorSettings,
+            _components: &RuntimeComponents,
+        ) -> SharedHttpConnector {
+            SharedHttpConnector::new(self.clone())
+        }
+    }
+
+    fn sdk_config(live: Option<Canned>) -> aws_config::SdkConfig {
+        let builder = aws_config::SdkConfig::builder()
+            .behavior_version(BehaviorVersion::latest())
+            .region(Region::new("us-east-1"))
+            .credentials_provider(SharedCredentialsProvider::new(Credentials::new(
+                "test", "test", None, None, "test",
+            )));
+        match live {
+            Some(live) => builder.http_client(live).build(),
+            None => builder.build(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_recorded_session_replays_offline() {
+        let path = std::env::temp_dir().join(format!(
+            "ec2-instance-manager-recording-{}.jsonl",
+            std::process::id()
+        ));
+
+        let recording = Traffic::record(&path).unwrap();
+        let live = SdkEc2::new(
+            &sdk_config(Some(Canned(DESCRIBE_INSTANCES))),
+            None,
+            Some(&recording),
+        )
+        .unwrap();
+        let recorded = live.list_instances().await.unwrap();
+        assert_eq!(recorded.len(), 1);
+
+        let replay = Traffic::replay(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        let offline = SdkEc2::new(&sdk_config(None), None, Some(&replay)).unwrap();
+        let replayed = offline.list_instances().await.unwrap();
+        assert_eq!(replayed[0].id, "i-1234567890abcdef0");
+        assert_eq!(replayed[0].instance_type, "m5.large");
+        assert_eq!(replayed[0].state, "running");
+
+        // Served again for later refreshes, but nothing else was recorded
+        assert_eq!(offline.list_instances().await.unwrap().len(), 1);
+        assert!(offline.get_instance_types().await.is_err());
+    }
+
+    #[test]
+    fn test_tape_serves_responses_in_recorded_order() {
+        let exchange = |body: &str| Exchange {
+            request: RequestKey {
+                service: "ec2".to_string(),
+                method: "POST".to_string(),
+                path: "/".to_string(),
+                target: None,
+                body: "Action=DescribeInstances".to_string(),
+            },
+            status: 200,
+            headers: vec![],
+            body: body.to_string(),
+        };
+        let content = [exchange("first"), exchange("second")]
+            .iter()
+            .map(|e| serde_json::to_string(e).unwrap())
+            .collect::<Vec<_>>()
+            .join("\n");
+        let tape = Tape::parse(&content).unwrap();
+        let key = exchange("").request;
+
+        assert_eq!(tape.next(&key).unwrap().body, "first");
+        assert_eq!(tape.next(&key).unwrap().body, "second");
+        assert_eq!(tape.next(&key).unwrap().body, "second");
+        assert!(Tape::parse("not json").is_err());
+    }
+}
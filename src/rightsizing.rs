@@ -0,0 +1,218 @@
+use crate::app::Prices;
+use crate::ec2::InstanceTypeInfo;
+use std::collections::HashMap;
+
+pub const HOURS_PER_MONTH: f64 = 730.0;
+
+// Size so that the observed peak lands at or below these utilizations on the new type
+const TARGET_CPU_PERCENT: f64 = 70.0;
+const TARGET_MEM_PERCENT: f64 = 80.0;
+// Above this peak the instance is considered undersized
+const OVERLOADED_CPU_PERCENT: f64 = 90.0;
+const MAX_SUGGESTIONS: usize = 8;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UtilizationStats {
+    pub days: u32,
+    pub cpu_avg: f64,
+    /// 95th percentile of the hourly CPU maxima.
+    pub cpu_peak: f64,
+    /// Peak memory used percent, if the CloudWatch agent publishes it.
+    pub mem_peak: Option<f64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecommendationKind {
+    Downsize,
+    Upsize,
+    CheaperFamily,
+}
+
+impl RecommendationKind {
+    pub fn label(self) -> &'static str {
+        match self {
+            RecommendationKind::Downsize => "smaller",
+            RecommendationKind::Upsize => "larger",
+            RecommendationKind::CheaperFamily => "cheaper family",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Recommendation {
+    pub instance_type: String,
+    pub kind: RecommendationKind,
+    pub vcpu: Option<i32>,
+    pub memory_mib: Option<i64>,
+    pub hourly_price: f64,
+    /// Projected monthly savings; negative when the suggestion costs more.
+    pub monthly_savings: f64,
+}
+
+fn family(instance_type: &str) -> &str {
+    instance_type.split('.').next().unwrap_or(instance_type)
+}
+
+/// Suggests types for `current_type` from its utilization, cheapest first. Only types with a
+/// known On-Demand price that support `architecture` are considered.
+pub fn recommend(
+    current_type: &str,
+    architecture: &str,
+    stats: &UtilizationStats,
+    specs: &HashMap<String, InstanceTypeInfo>,
+    prices: &HashMap<String, Prices>,
+) -> Vec<Recommendation> {
+    let (Some(current), Some(current_price)) = (
+        specs.get(current_type),
+        prices.get(current_type).and_then(|p| p.on_demand),
+    ) else {
+        return vec![];
+    };
+    let (Some(current_vcpu), Some(current_mem)) = (current.vcpu, current.memory_mib) else {
+        return vec![];
+    };
+
+    let needed_vcpu = current_vcpu as f64 * stats.cpu_peak / TARGET_CPU_PERCENT;
+    // Without memory metrics we can't tell how much is used, so never go below the current size
+    let needed_mem = match stats.mem_peak {
+        Some(mem) => current_mem as f64 * mem / TARGET_MEM_PERCENT,
+        None => current_mem as f64,
+    };
+    let overloaded = stats.cpu_peak >= OVERLOADED_CPU_PERCENT;
+
+    let mut recommendations: Vec<Recommendation> = specs
+        .values()
+        .filter(|t| t.name != current_type)
+        .filter(|t| t.architectures.iter().any(|a| a == architecture))
+        .filter_map(|t| {
+            let price = prices.get(&t.name).and_then(|p| p.on_demand)?;
+            let vcpu = t.vcpu?;
+            let mem = t.memory_mib?;
+            if (vcpu as f64) < needed_vcpu || (mem as f64) < needed_mem {
+                return None;
+            }
+
+            let kind = if overloaded {
+                if vcpu <= current_vcpu {
+                    return None;
+                }
+                RecommendationKind::Upsize
+            } else if price >= current_price {
+                return None;
+            } else if family(&t.name) == family(current_type) {
+                RecommendationKind::Downsize
+            } else {
+                RecommendationKind::CheaperFamily
+            };
+
+            Some(Recommendation {
+                instance_type: t.name.clone(),
+                kind,
+                vcpu: Some(vcpu),
+                memory_mib: Some(mem),
+                hourly_price: price,
+                monthly_savings: (current_price - price) * HOURS_PER_MONTH,
+            })
+        })
+        .collect();
+
+    recommendations.sort_by(|a, b| {
+        a.hourly_price
+            .partial_cmp(&b.hourly_price)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then_with(|| a.instance_type.cmp(&b.instance_type))
+    });
+    recommendations.truncate(MAX_SUGGESTIONS);
+    recommendations
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec(name: &str, vcpu: i32, memory_mib: i64) -> InstanceTypeInfo {
+        InstanceTypeInfo {
+            name: name.to_string(),
+            architectures: vec!["x86_64".to_string()],
+            vcpu: Some(vcpu),
+            memory_mib: Some(memory_mib),
+        }
+    }
+
+    fn catalog() -> (HashMap<String, InstanceTypeInfo>, HashMap<String, Prices>) {
+        let types = [
+            (spec("m5.large", 2, 8192), 0.096),
+            (spec("m5.xlarge", 4, 16384), 0.192),
+            (spec("m5.2xlarge", 8, 32768), 0.384),
+            (spec("m6a.xlarge", 4, 16384), 0.1728),
+            (spec("c5.xlarge", 4, 8192), 0.17),
+        ];
+        let mut specs = HashMap::new();
+        let mut prices = HashMap::new();
+        for (t, p) in types {
+            prices.insert(
+                t.name.clone(),
+                Prices {
+                    on_demand: Some(p),
+                    spot: None,
+                },
+            );
+            specs.insert(t.name.clone(), t);
+        }
+        (specs, prices)
+    }
+
+    fn stats(cpu_peak: f64, mem_peak: Option<f64>) -> UtilizationStats {
+        UtilizationStats {
+            days: 14,
+            cpu_avg: cpu_peak / 2.0,
+            cpu_peak,
+            mem_peak,
+        }
+    }
+
+    #[test]
+    fn test_idle_instance_downsizes() {
+        let (specs, prices) = catalog();
+        let recs = recommend(
+            "m5.xlarge",
+            "x86_64",
+            &stats(20.0, Some(30.0)),
+            &specs,
+            &prices,
+        );
+
+        assert_eq!(recs[0].instance_type, "m5.large");
+        assert_eq!(recs[0].kind, RecommendationKind::Downsize);
+        assert!((recs[0].monthly_savings - 0.096 * HOURS_PER_MONTH).abs() < 1e-9);
+        assert!(recs.iter().any(
+            |r| r.instance_type == "m6a.xlarge" && r.kind == RecommendationKind::CheaperFamily
+        ));
+    }
+
+    #[test]
+    fn test_memory_unknown_keeps_memory() {
+        let (specs, prices) = catalog();
+        let recs = recommend("m5.xlarge", "x86_64", &stats(20.0, None), &specs, &prices);
+
+        // m5.large and c5.xlarge have less memory than m5.xlarge
+        assert!(recs.iter().all(|r| r.memory_mib >= Some(16384)));
+    }
+
+    #[test]
+    fn test_overloaded_instance_upsizes() {
+        let (specs, prices) = catalog();
+        let recs = recommend(
+            "m5.xlarge",
+            "x86_64",
+            &stats(98.0, Some(50.0)),
+            &specs,
+            &prices,
+        );
+
+        assert_eq!(recs.len(), 1);
+        assert_eq!(recs[0].instance_type, "m5.2xlarge");
+        assert_eq!(recs[0].kind, RecommendationKind::Upsize);
+        assert!(recs[0].monthly_savings < 0.0);
+    }
+}
@@ -0,0 +1,445 @@
+use crate::config::{InstanceSelector, ResizeScheduleRule, ScheduleRule};
+use crate::ec2::InstanceInfo;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+use chrono_tz::Tz;
+
+// Cron expressions that never fire within a year are treated as never firing
+const SEARCH_DAYS: i64 = 366;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduledAction {
+    Start,
+    Stop,
+    Resize(String), // Target instance type
+}
+
+impl ScheduledAction {
+    pub fn label(&self) -> String {
+        match self {
+            ScheduledAction::Start => "start".to_string(),
+            ScheduledAction::Stop => "stop".to_string(),
+            ScheduledAction::Resize(t) => format!("resize {}", t),
+        }
+    }
+}
+
+fn parse_timezone(rule_name: &str, timezone: Option<&str>) -> Result<Tz> {
+    match timezone {
+        Some(tz) => tz
+            .parse::<Tz>()
+            .map_err(|e| anyhow::anyhow!("{}: invalid timezone '{}': {}", rule_name, tz, e)),
+        None => Ok(Tz::UTC),
+    }
+}
+
+/// A five-field cron expression: minute, hour, day of month, month, day of week.
+/// Fields accept `*`, numbers, ranges (`1-5`), lists (`1,3,5`) and steps (`*/15`, `8-18/2`).
+/// Day of week is 0-7 with both 0 and 7 meaning Sunday.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronExpr {
+    minutes: Vec<u32>,
+    hours: Vec<u32>,
+    days_of_month: Vec<u32>,
+    months: Vec<u32>,
+    days_of_week: Vec<u32>,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+fn parse_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>> {
+    let mut values = Vec::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((r, s)) => (r, s.parse::<u32>().context("invalid step")?),
+            None => (part, 1),
+        };
+        if step == 0 {
+            anyhow::bail!("step must be positive in '{}'", part);
+        }
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((a, b)) = range.split_once('-') {
+            (a.parse::<u32>()?, b.parse::<u32>()?)
+        } else {
+            let v = range.parse::<u32>()?;
+            // "5/10" means from 5 to the end in steps of 10
+            if part.contains('/') { (v, max) } else { (v, v) }
+        };
+        if start < min || end > max || start > end {
+            anyhow::bail!("'{}' is outside {}-{}", part, min, max);
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    values.sort_unstable();
+    values.dedup();
+    Ok(values)
+}
+
+impl CronExpr {
+    pub fn parse(expr: &str) -> Result<Self> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        let &[minute, hour, dom, month, dow] = fields.as_slice() else {
+            anyhow::bail!("expected 5 fields in cron expression '{}'", expr);
+        };
+        let mut days_of_week = parse_field(dow, 0, 7)
+            .with_context(|| format!("day of week in '{}'", expr))?
+            .into_iter()
+            .map(|d| d % 7)
+            .collect::<Vec<_>>();
+        days_of_week.sort_unstable();
+        days_of_week.dedup();
+
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59).with_context(|| format!("minute in '{}'", expr))?,
+            hours: parse_field(hour, 0, 23).with_context(|| format!("hour in '{}'", expr))?,
+            days_of_month: parse_field(dom, 1, 31)
+                .with_context(|| format!("day of month in '{}'", expr))?,
+            months: parse_field(month, 1, 12).with_context(|| format!("month in '{}'", expr))?,
+            days_of_week,
+            dom_restricted: dom != "*",
+            dow_restricted: dow != "*",
+        })
+    }
+
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        if !self.months.contains(&date.month()) {
+            return false;
+        }
+        let dom = self.days_of_month.contains(&date.day());
+        let dow = self
+            .days_of_week
+            .contains(&date.weekday().num_days_from_sunday());
+        // Standard cron: if both day fields are restricted, either may match
+        match (self.dom_restricted, self.dow_restricted) {
+            (true, true) => dom || dow,
+            (true, false) => dom,
+            (false, true) => dow,
+            (false, false) => true,
+        }
+    }
+
+    /// All firing times on `date` in `tz`, in order. Local times skipped by a DST change
+    /// don't fire; repeated ones fire once.
+    fn firings_on(&self, date: NaiveDate, tz: Tz) -> Vec<DateTime<Utc>> {
+        if !self.matches_day(date) {
+            return vec![];
+        }
+        let mut times = Vec::new();
+        for &h in &self.hours {
+            for &m in &self.minutes {
+                if let Some(naive) = date.and_hms_opt(h, m, 0)
+                    && let Some(local) = tz.from_local_datetime(&naive).earliest()
+                {
+                    times.push(local.with_timezone(&Utc));
+                }
+            }
+        }
+        times
+    }
+
+    /// First firing strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>, tz: Tz) -> Option<DateTime<Utc>> {
+        let start = after.with_timezone(&tz).date_naive();
+        (0..=SEARCH_DAYS)
+            .filter_map(|d| start.checked_add_signed(Duration::days(d)))
+            .flat_map(|date| self.firings_on(date, tz))
+            .find(|t| *t > after)
+    }
+
+    /// Last firing at or before `at`.
+    pub fn last_at_or_before(&self, at: DateTime<Utc>, tz: Tz) -> Option<DateTime<Utc>> {
+        let start = at.with_timezone(&tz).date_naive();
+        (0..=SEARCH_DAYS)
+            .filter_map(|d| start.checked_sub_signed(Duration::days(d)))
+            .flat_map(|date| self.firings_on(date, tz).into_iter().rev())
+            .find(|t| *t <= at)
+    }
+}
+
+impl InstanceSelector {
+    /// Every given criterion must match; a selector with no criteria matches nothing.
+    pub fn matches(&self, instance: &InstanceInfo) -> bool {
+        if self.id.is_none() && self.name.is_none() && self.tag.is_none() {
+            return false;
+        }
+        if let Some(id) = &self.id
+            && *id != instance.id
+        {
+            return false;
+        }
+        if let Some(name) = &self.name
+            && instance.name.as_deref() != Some(name.as_str())
+        {
+            return false;
+        }
+        if let Some(tag) = &self.tag {
+            let (key, value) = tag.split_once('=').unwrap_or((tag.as_str(), ""));
+            match instance.tags.get(key) {
+                Some(v) if value.is_empty() || v == value => {}
+                _ => return false,
+            }
+        }
+        true
+    }
+}
+
+/// A schedule rule with its timezone and cron expressions parsed.
+#[derive(Debug, Clone)]
+pub struct CompiledRule {
+    pub name: String,
+    pub select: InstanceSelector,
+    pub tz: Tz,
+    pub on: Option<CronExpr>,
+    pub off: Option<CronExpr>,
+}
+
+impl CompiledRule {
+    pub fn compile(index: usize, rule: &ScheduleRule) -> Result<Self> {
+        let name = rule
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("schedule #{}", index + 1));
+        let tz = parse_timezone(&name, rule.timezone.as_deref())?;
+        let on = rule
+            .on
+            .as_deref()
+            .map(CronExpr::parse)
+            .transpose()
+            .with_context(|| format!("{}: invalid 'on'", name))?;
+        let off = rule
+            .off
+            .as_deref()
+            .map(CronExpr::parse)
+            .transpose()
+            .with_context(|| format!("{}: invalid 'off'", name))?;
+        Ok(Self {
+            name,
+            select: rule.select.clone(),
+            tz,
+            on,
+            off,
+        })
+    }
+
+    pub fn compile_all(rules: &[ScheduleRule]) -> Result<Vec<Self>> {
+        rules
+            .iter()
+            .enumerate()
+            .map(|(i, r)| Self::compile(i, r))
+            .collect()
+    }
+
+    /// The next start or stop after `now`.
+    pub fn next_action(&self, now: DateTime<Utc>) -> Option<(ScheduledAction, DateTime<Utc>)> {
+        let on = self
+            .on
+            .as_ref()
+            .and_then(|c| c.next_after(now, self.tz))
+            .map(|t| (ScheduledAction::Start, t));
+        let off = self
+            .off
+            .as_ref()
+            .and_then(|c| c.next_after(now, self.tz))
+            .map(|t| (ScheduledAction::Stop, t));
+        [on, off].into_iter().flatten().min_by_key(|(_, t)| *t)
+    }
+
+    /// The most recent start or stop at or before `now`, which defines the desired state.
+    pub fn last_action(&self, now: DateTime<Utc>) -> Option<(ScheduledAction, DateTime<Utc>)> {
+        let on = self
+            .on
+            .as_ref()
+            .and_then(|c| c.last_at_or_before(now, self.tz))
+            .map(|t| (ScheduledAction::Start, t));
+        let off = self
+            .off
+            .as_ref()
+            .and_then(|c| c.last_at_or_before(now, self.tz))
+            .map(|t| (ScheduledAction::Stop, t));
+        [on, off].into_iter().flatten().max_by_key(|(_, t)| *t)
+    }
+}
+
+/// A resize schedule with its timezone and window expressions parsed.
+#[derive(Debug, Clone)]
+pub struct CompiledResizeRule {
+    pub name: String,
+    pub select: InstanceSelector,
+    pub tz: Tz,
+    pub windows: Vec<(CronExpr, String)>, // Window start, target type
+}
+
+impl CompiledResizeRule {
+    pub fn compile(index: usize, rule: &ResizeScheduleRule) -> Result<Self> {
+        let name = rule
+            .name
+            .clone()
+            .unwrap_or_else(|| format!("resize schedule #{}", index + 1));
+        let tz = parse_timezone(&name, rule.timezone.as_deref())?;
+        let windows = rule
+            .windows
+            .iter()
+            .map(|w| {
+                CronExpr::parse(&w.at)
+                    .map(|c| (c, w.instance_type.clone()))
+                    .with_context(|| format!("{}: invalid window '{}'", name, w.at))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self {
+            name,
+            select: rule.select.clone(),
+            tz,
+            windows,
+        })
+    }
+
+    pub fn compile_all(rules: &[ResizeScheduleRule]) -> Result<Vec<Self>> {
+        rules
+            .iter()
+            .enumerate()
+            .map(|(i, r)| Self::compile(i, r))
+            .collect()
+    }
+
+    /// The target type of the window in effect at `now`, and when that window began.
+    pub fn current_target(&self, now: DateTime<Utc>) -> Option<(String, DateTime<Utc>)> {
+        self.windows
+            .iter()
+            .filter_map(|(c, t)| c.last_at_or_before(now, self.tz).map(|at| (t.clone(), at)))
+            .max_by_key(|(_, at)| *at)
+    }
+
+    pub fn next_action(&self, now: DateTime<Utc>) -> Option<(ScheduledAction, DateTime<Utc>)> {
+        self.windows
+            .iter()
+            .filter_map(|(c, t)| {
+                c.next_after(now, self.tz)
+                    .map(|at| (ScheduledAction::Resize(t.clone()), at))
+            })
+            .min_by_key(|(_, at)| *at)
+    }
+}
+
+/// The earliest upcoming action for `instance` across all rules that select it.
+pub fn next_action_for(
+    rules: &[CompiledRule],
+    resize_rules: &[CompiledResizeRule],
+    instance: &InstanceInfo,
+    now: DateTime<Utc>,
+) -> Option<(ScheduledAction, DateTime<Utc>)> {
+    let power = rules
+        .iter()
+        .filter(|r| r.select.matches(instance))
+        .filter_map(|r| r.next_action(now));
+    let resize = resize_rules
+        .iter()
+        .filter(|r| r.select.matches(instance))
+        .filter_map(|r| r.next_action(now));
+    power.chain(resize).min_by_key(|(_, t)| *t)
+}
+
+/// Formats `t` in the user's local zone, with the weekday unless it is today.
+pub fn format_local(t: DateTime<Utc>) -> String {
+    let local = t.with_timezone(&chrono::Local);
+    if local.date_naive() == chrono::Local::now().date_naive() {
+        format!("{:02}:{:02}", local.hour(), local.minute())
+    } else {
+        local.format("%a %H:%M").to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_fields() {
+        let c = CronExpr::parse("*/15 8-18/5 * * 1-5").unwrap();
+        assert_eq!(c.minutes, vec![0, 15, 30, 45]);
+        assert_eq!(c.hours, vec![8, 13, 18]);
+        assert_eq!(c.days_of_week, vec![1, 2, 3, 4, 5]);
+
+        let sunday = CronExpr::parse("0 0 * * 7").unwrap();
+        assert_eq!(sunday.days_of_week, vec![0]);
+
+        assert!(CronExpr::parse("0 8 * *").is_err());
+        assert!(CronExpr::parse("60 8 * * *").is_err());
+        assert!(CronExpr::parse("0 8 * * 1-5/0").is_err());
+    }
+
+    #[test]
+    fn test_next_after_skips_weekend() {
+        let c = CronExpr::parse("0 8 * * 1-5").unwrap();
+        // Friday 2026-10-16 09:00 UTC -> Monday 08:00
+        let next = c.next_after(utc("2026-10-16T09:00:00Z"), Tz::UTC).unwrap();
+        assert_eq!(next, utc("2026-10-19T08:00:00Z"));
+    }
+
+    #[test]
+    fn test_timezone_is_applied() {
+        let c = CronExpr::parse("0 19 * * *").unwrap();
+        // 19:00 in Berlin is 17:00 UTC during summer time
+        let next = c
+            .next_after(utc("2026-07-01T00:00:00Z"), chrono_tz::Europe::Berlin)
+            .unwrap();
+        assert_eq!(next, utc("2026-07-01T17:00:00Z"));
+    }
+
+    #[test]
+    fn test_last_action_defines_desired_state() {
+        let rule = CompiledRule::compile(
+            0,
+            &ScheduleRule {
+                name: None,
+                select: InstanceSelector::default(),
+                timezone: None,
+                on: Some("0 8 * * *".to_string()),
+                off: Some("0 19 * * *".to_string()),
+            },
+        )
+        .unwrap();
+
+        let (action, at) = rule.last_action(utc("2026-10-18T12:00:00Z")).unwrap();
+        assert_eq!(action, ScheduledAction::Start);
+        assert_eq!(at, utc("2026-10-18T08:00:00Z"));
+
+        let (action, at) = rule.next_action(utc("2026-10-18T12:00:00Z")).unwrap();
+        assert_eq!(action, ScheduledAction::Stop);
+        assert_eq!(at, utc("2026-10-18T19:00:00Z"));
+    }
+
+    #[test]
+    fn test_resize_target_follows_latest_window() {
+        let rule = CompiledResizeRule::compile(
+            0,
+            &ResizeScheduleRule {
+                name: None,
+                select: InstanceSelector::default(),
+                timezone: None,
+                windows: vec![
+                    crate::config::ResizeWindow {
+                        at: "0 8 * * *".to_string(),
+                        instance_type: "c6a.4xlarge".to_string(),
+                    },
+                    crate::config::ResizeWindow {
+                        at: "0 20 * * *".to_string(),
+                        instance_type: "c6a.large".to_string(),
+                    },
+                ],
+            },
+        )
+        .unwrap();
+
+        let (target, at) = rule.current_target(utc("2026-10-18T23:00:00Z")).unwrap();
+        assert_eq!(target, "c6a.large");
+        assert_eq!(at, utc("2026-10-18T20:00:00Z"));
+
+        let (target, _) = rule.current_target(utc("2026-10-19T09:30:00Z")).unwrap();
+        assert_eq!(target, "c6a.4xlarge");
+    }
+}
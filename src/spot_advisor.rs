@@ -0,0 +1,184 @@
+use anyhow::Result;
+use directories::ProjectDirs;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs;
+
+pub const DEFAULT_SOURCE: &str = "https://spot-bid-advisor.s3.amazonaws.com/spot-advisor-data.json";
+
+// The advisor feed is regenerated by AWS a few times a day, so a day-old copy is good enough.
+const CACHE_TTL: Duration = Duration::from_secs(24 * 60 * 60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpotAdvice {
+    /// Interruption frequency bucket label, e.g. "<5%" or "5-10%".
+    pub interruption: String,
+    /// Bucket index, 0 being the least frequently interrupted.
+    pub range_index: u32,
+    /// Savings over On-Demand in percent.
+    pub savings_percent: u32,
+}
+
+/// Spot Instance Advisor data, keyed by region and instance type (Linux only).
+#[derive(Debug, Clone, Default)]
+pub struct SpotAdvisor {
+    regions: HashMap<String, HashMap<String, SpotAdvice>>,
+}
+
+#[derive(Deserialize)]
+struct RawFeed {
+    ranges: Vec<RawRange>,
+    spot_advisor: HashMap<String, HashMap<String, HashMap<String, RawAdvice>>>,
+}
+
+#[derive(Deserialize)]
+struct RawRange {
+    index: u32,
+    label: String,
+}
+
+#[derive(Deserialize)]
+struct RawAdvice {
+    s: u32,
+    r: u32,
+}
+
+impl SpotAdvisor {
+    pub fn parse(json: &str) -> Result<Self> {
+        let feed: RawFeed = serde_json::from_str(json)?;
+        let labels: HashMap<u32, String> = feed
+            .ranges
+            .into_iter()
+            .map(|r| (r.index, r.label))
+            .collect();
+
+        let mut regions = HashMap::new();
+        for (region, by_os) in feed.spot_advisor {
+            let Some(linux) = by_os.get("Linux") else {
+                continue;
+            };
+            let types = linux
+                .iter()
+                .map(|(t, a)| {
+                    let interruption = labels.get(&a.r).cloned().unwrap_or_else(|| "-".to_string());
+                    (
+                        t.clone(),
+                        SpotAdvice {
+                            interruption,
+                            range_index: a.r,
+                            savings_percent: a.s,
+                        },
+                    )
+                })
+                .collect();
+            regions.insert(region, types);
+        }
+        Ok(Self { regions })
+    }
+
+    pub fn get(&self, region: &str, instance_type: &str) -> Option<&SpotAdvice> {
+        self.regions.get(region).and_then(|r| r.get(instance_type))
+    }
+
+    /// Loads the advisor data from `source`, which is either an http(s) URL or a local file path.
+    /// Remote data is cached on disk and reused while fresh; a stale cache is used if the
+    /// download fails.
+    pub async fn load(source: &str) -> Result<Self> {
+        if !source.starts_with("http://") && !source.starts_with("https://") {
+            let content = fs::read_to_string(source).await?;
+            return Self::parse(&content);
+        }
+
+        let cache_path = Self::get_cache_path();
+        if let Some(path) = &cache_path
+            && let Ok(meta) = fs::metadata(path).await
+            && let Ok(modified) = meta.modified()
+            && modified.elapsed().map(|e| e < CACHE_TTL).unwrap_or(false)
+            && let Ok(content) = fs::read_to_string(path).await
+            && let Ok(advisor) = Self::parse(&content)
+        {
+            return Ok(advisor);
+        }
+
+        match Self::download(source).await {
+            Ok(content) => {
+                let advisor = Self::parse(&content)?;
+                if let Some(path) = &cache_path {
+                    if let Some(parent) = path.parent() {
+                        let _ = fs::create_dir_all(parent).await;
+                    }
+                    let _ = fs::write(path, &content).await;
+                }
+                Ok(advisor)
+            }
+            Err(e) => {
+                // Fall back to whatever we have cached, however old
+                if let Some(path) = &cache_path
+                    && let Ok(content) = fs::read_to_string(path).await
+                {
+                    return Self::parse(&content);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn download(url: &str) -> Result<String> {
+        let content = reqwest::get(url).await?.error_for_status()?.text().await?;
+        Ok(content)
+    }
+
+    fn get_cache_path() -> Option<PathBuf> {
+        ProjectDirs::from("", "", "ec2-instance-manager")
+            .map(|dirs| dirs.cache_dir().join("spot-advisor-data.json"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SAMPLE: &str = r#"{
+        "global_rate": "<10%",
+        "instance_types": {},
+        "ranges": [
+            {"index": 0, "label": "<5%", "dots": 0, "max": 5},
+            {"index": 1, "label": "5-10%", "dots": 1, "max": 11},
+            {"index": 4, "label": ">20%", "dots": 4, "max": 100}
+        ],
+        "spot_advisor": {
+            "us-east-1": {
+                "Linux": {
+                    "t3.micro": {"s": 70, "r": 0},
+                    "m5.large": {"s": 55, "r": 4}
+                },
+                "Windows": {
+                    "t3.micro": {"s": 40, "r": 1}
+                }
+            }
+        }
+    }"#;
+
+    #[test]
+    fn test_parse_linux_advice() {
+        let advisor = SpotAdvisor::parse(SAMPLE).unwrap();
+
+        let t3 = advisor.get("us-east-1", "t3.micro").unwrap();
+        assert_eq!(t3.interruption, "<5%");
+        assert_eq!(t3.savings_percent, 70);
+
+        let m5 = advisor.get("us-east-1", "m5.large").unwrap();
+        assert_eq!(m5.interruption, ">20%");
+        assert_eq!(m5.range_index, 4);
+    }
+
+    #[test]
+    fn test_missing_region_or_type() {
+        let advisor = SpotAdvisor::parse(SAMPLE).unwrap();
+
+        assert!(advisor.get("eu-west-1", "t3.micro").is_none());
+        assert!(advisor.get("us-east-1", "c5.large").is_none());
+    }
+}
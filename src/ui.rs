@@ -1,21 +1,76 @@
-use crate::app::{App, AppState, Prices};
-use crate::ec2::InstanceTypeInfo;
+use crate::app::{App, AppState, Load, PendingAction, Prices};
+use crate::audit::AuditRecord;
+use crate::ec2::{InstanceTypeInfo, Permission, PermissionCheck, SpotRequestInfo};
+use crate::errors::ErrorKind;
+use crate::idle::IdleCandidate;
+use crate::operations::Status;
+use crate::rightsizing::{Recommendation, UtilizationStats};
+use crate::spot_advisor::SpotAdvisor;
 use ratatui::{
     Frame,
     layout::{Alignment, Constraint, Direction, Layout, Rect},
     style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Cell, Paragraph, Row, Table, TableState},
+    text::{Line, Span},
+    widgets::{Block, Borders, Cell, Paragraph, Row, Sparkline, Table, TableState},
 };
 use std::collections::HashMap;
 
 pub fn draw(f: &mut Frame, app: &mut App) {
+    let banner_height = if app.credentials_problem.is_some() {
+        1
+    } else {
+        0
+    };
     let chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Min(0), Constraint::Length(1)])
+        .constraints([
+            Constraint::Length(banner_height),
+            Constraint::Min(0),
+            Constraint::Length(1),
+        ])
         .split(f.area());
 
-    draw_list(f, app, chunks[0]);
-    draw_statusbar(f, app, chunks[1]);
+    if let Some(kind) = app.credentials_problem {
+        let problem = if kind == ErrorKind::ExpiredCredentials {
+            "AWS credentials expired"
+        } else {
+            "AWS credentials missing"
+        };
+        let banner = Paragraph::new(format!(
+            " {}. Auto-refresh paused. l: Log In Again | R: Reload Credentials",
+            problem
+        ))
+        .style(
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        );
+        f.render_widget(banner, chunks[0]);
+    }
+
+    let main = if app.operations.is_empty() {
+        chunks[1]
+    } else {
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(44)])
+            .split(chunks[1]);
+        draw_operations(f, app, columns[1]);
+        columns[0]
+    };
+
+    if app.show_metrics {
+        let panes = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(8)])
+            .split(main);
+        draw_list(f, app, panes[0]);
+        draw_metrics(f, app, panes[1]);
+    } else {
+        draw_list(f, app, main);
+    }
+    draw_statusbar(f, app, chunks[2]);
 
     if let AppState::SelectingType {
         input,
@@ -24,10 +79,14 @@ pub fn draw(f: &mut Frame, app: &mut App) {
         default_mode_active,
     } = &app.state
     {
+        let title = selector_title(app);
         draw_selecting_type(
             f,
+            &title,
             &app.type_prices,
             &app.instance_type_map,
+            app.spot_advisor.as_ref(),
+            &app.region,
             input,
             options,
             *selected_index,
@@ -39,8 +98,95 @@ pub fn draw(f: &mut Frame, app: &mut App) {
         draw_confirm_reboot(f, instance_id);
     }
 
+    if let AppState::ConfirmAction {
+        action,
+        expected,
+        input,
+    } = &app.state
+    {
+        draw_confirm_action(f, action, expected.as_deref(), input);
+    }
+
+    if let AppState::SpotDetails {
+        instance_id,
+        request,
+    } = &app.state
+    {
+        draw_spot_details(f, instance_id, request.as_ref());
+    }
+
+    if let AppState::AuditLog {
+        records,
+        instance_filter,
+        action_filter,
+        selected_index,
+    } = &app.state
+    {
+        draw_audit_log(
+            f,
+            records.as_deref(),
+            instance_filter,
+            action_filter.map(|i| crate::audit::ACTIONS[i]),
+            *selected_index,
+        );
+    }
+
+    if let AppState::PermissionReport {
+        instance_id,
+        checks,
+    } = &app.state
+    {
+        draw_permission_report(f, instance_id, checks.as_deref());
+    }
+
+    if let AppState::Recommendations {
+        instance_id,
+        stats,
+        recommendations,
+        selected_index,
+    } = &app.state
+    {
+        draw_recommendations(
+            f,
+            instance_id,
+            stats.as_ref(),
+            recommendations,
+            *selected_index,
+        );
+    }
+
+    if let AppState::IdleInstances {
+        candidates,
+        selected_index,
+        scanning,
+    } = &app.state
+    {
+        draw_idle_instances(f, &app.type_prices, candidates, *selected_index, *scanning);
+    }
+
     if let AppState::Processing(msg) = &app.state {
-        draw_processing(f, msg);
+        let detail = app.error_detail.as_deref().filter(|_| app.error_expanded);
+        draw_processing(f, msg, detail);
+    }
+}
+
+/// Title for the type selector, warning when the selected instance is a spot instance.
+fn selector_title(app: &App) -> String {
+    let instance = app
+        .list_state
+        .selected()
+        .and_then(|i| app.filtered_instances.get(i));
+    match instance {
+        Some(i) if i.is_spot() => match app.spot_requests.get(&i.id) {
+            Some(r) if r.is_persistent() => {
+                "Select Instance Type (spot: relaunches from the persistent request)".to_string()
+            }
+            Some(_) => {
+                "Select Instance Type (spot: one-time request, cannot be resized)".to_string()
+            }
+            None => "Select Instance Type (spot: checking request type...)".to_string(),
+        },
+        _ => "Select Instance Type".to_string(),
     }
 }
 
@@ -50,10 +196,17 @@ fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
         "Name",
         "Type",
         "State",
+        "System",
+        "Instance",
+        "EBS",
         "AZ",
         "Public IP",
         "Arch",
+        "Hib",
+        "Protect",
+        "Market",
         "Runtime",
+        "Next Action",
     ]
     .iter()
     .map(|h| Cell::from(*h).style(Style::default().fg(Color::Yellow)));
@@ -67,10 +220,18 @@ fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
     let mut w_name = 4;
     let mut w_type = 4;
     let mut w_state = 5;
+    let mut w_system = 6;
+    let mut w_instance = 8;
+    let mut w_ebs = 3;
     let mut w_az = 2;
     let mut w_ip = 9;
     let mut w_arch = 4;
+    let mut w_hib = 3;
+    let mut w_protect = 7;
+    let mut w_market = 6;
     let mut w_uptime = 6;
+    let mut w_next = 11;
+    let now = chrono::Utc::now();
 
     let rows: Vec<Row> = app
         .filtered_instances
@@ -80,6 +241,26 @@ fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
             let az = i.availability_zone.as_deref().unwrap_or("-");
             let public_ip = i.public_ip.as_deref().unwrap_or("-");
             let arch = i.architecture.as_deref().unwrap_or("-");
+            let market = if i.is_spot() { "spot" } else { "on-demand" };
+            let hibernation = if i.hibernation_configured { "yes" } else { "-" };
+            let protect = match app.protection.get(&i.id) {
+                Some(Some(p)) => match (p.stop, p.termination) {
+                    (true, true) => "stop,term",
+                    (true, false) => "stop",
+                    (false, true) => "term",
+                    (false, false) => "-",
+                },
+                _ => "?",
+            };
+            let state = if i.is_hibernated() {
+                "stop-hibernated".to_string()
+            } else {
+                i.state.clone()
+            };
+            let checks = app.status_checks.get(&i.id);
+            let system = status_check(checks.map(|c| c.system.as_str()));
+            let instance = status_check(checks.map(|c| c.instance.as_str()));
+            let ebs = status_check(checks.map(|c| c.ebs.as_str()));
 
             let uptime = if i.state.eq_ignore_ascii_case("running") {
                 if let Some(launch_time) = i.launch_time {
@@ -112,27 +293,58 @@ fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
             w_id = w_id.max(i.id.len());
             w_name = w_name.max(name.len());
             w_type = w_type.max(i.instance_type.len());
-            w_state = w_state.max(i.state.len());
+            w_state = w_state.max(state.len());
+            w_system = w_system.max(system.0.len());
+            w_instance = w_instance.max(instance.0.len());
+            w_ebs = w_ebs.max(ebs.0.len());
             w_az = w_az.max(az.len());
             w_ip = w_ip.max(public_ip.len());
             w_arch = w_arch.max(arch.len());
+            w_hib = w_hib.max(hibernation.len());
+            w_protect = w_protect.max(protect.len());
+            w_market = w_market.max(market.len());
             w_uptime = w_uptime.max(uptime.len());
 
+            let next_action =
+                crate::schedule::next_action_for(&app.schedule_rules, &app.resize_rules, i, now)
+                    .map(|(action, at)| {
+                        format!("{} {}", action.label(), crate::schedule::format_local(at))
+                    })
+                    .unwrap_or_else(|| "-".to_string());
+            w_next = w_next.max(next_action.len());
+
             let cells = vec![
                 Cell::from(i.id.clone()),
                 Cell::from(name.to_string()),
                 Cell::from(i.instance_type.clone()).style(Style::default().fg(Color::Cyan)),
-                Cell::from(i.state.clone()).style(if i.state == "running" {
+                Cell::from(state).style(if i.state == "running" {
                     Style::default().fg(Color::Green)
+                } else if i.is_hibernated() {
+                    Style::default().fg(Color::Blue)
                 } else if i.state == "stopped" {
                     Style::default().fg(Color::Red)
                 } else {
                     Style::default()
                 }),
+                Cell::from(system.0).style(system.1),
+                Cell::from(instance.0).style(instance.1),
+                Cell::from(ebs.0).style(ebs.1),
                 Cell::from(az.to_string()),
                 Cell::from(public_ip.to_string()),
                 Cell::from(arch.to_string()),
+                Cell::from(hibernation),
+                Cell::from(protect).style(if protect.starts_with("stop") {
+                    Style::default().fg(Color::Yellow)
+                } else {
+                    Style::default()
+                }),
+                Cell::from(market).style(if i.is_spot() {
+                    Style::default().fg(Color::Magenta)
+                } else {
+                    Style::default()
+                }),
                 Cell::from(uptime),
+                Cell::from(next_action),
             ];
             Row::new(cells).height(1)
         })
@@ -143,19 +355,28 @@ fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
         Constraint::Length(w_name as u16 + 2),
         Constraint::Length(w_type as u16 + 2),
         Constraint::Length(w_state as u16 + 2),
+        Constraint::Length(w_system as u16 + 2),
+        Constraint::Length(w_instance as u16 + 2),
+        Constraint::Length(w_ebs as u16 + 2),
         Constraint::Length(w_az as u16 + 2),
         Constraint::Length(w_ip as u16 + 2),
         Constraint::Length(w_arch as u16 + 2),
+        Constraint::Length(w_hib as u16 + 2),
+        Constraint::Length(w_protect as u16 + 2),
+        Constraint::Length(w_market as u16 + 2),
         Constraint::Length(w_uptime as u16 + 2),
+        Constraint::Length(w_next as u16 + 2),
     ];
 
     let table = Table::new(rows, widths)
         .header(header)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("EC2 Instances"),
-        )
+        .block(Block::default().borders(Borders::ALL).title(
+            if app.loading.contains(&Load::Instances) {
+                "EC2 Instances (loading...)"
+            } else {
+                "EC2 Instances"
+            },
+        ))
         .row_highlight_style(
             Style::default()
                 .add_modifier(Modifier::BOLD)
@@ -165,47 +386,281 @@ fn draw_list(f: &mut Frame, app: &mut App, area: Rect) {
     f.render_stateful_widget(table, area, &mut app.list_state);
 }
 
+/// Short label and colour for one status check. Instances that are not running have none.
+fn status_check(status: Option<&str>) -> (&'static str, Style) {
+    match status {
+        Some("ok") => ("ok", Style::default().fg(Color::Green)),
+        Some("impaired") => (
+            "impaired",
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+        ),
+        Some("initializing") => ("init", Style::default().fg(Color::Yellow)),
+        Some("insufficient-data") => ("no data", Style::default().fg(Color::Yellow)),
+        _ => ("-", Style::default()),
+    }
+}
+
+/// Side panel listing running and queued operations, oldest first.
+fn draw_operations(f: &mut Frame, app: &App, area: Rect) {
+    let mut lines = Vec::new();
+    for op in app.operations.all() {
+        let (status, style) = match op.status {
+            Status::Running => (op.step.as_str(), Style::default().fg(Color::Green)),
+            Status::Queued => ("Queued", Style::default().fg(Color::DarkGray)),
+            Status::Cancelling => ("Cancelling...", Style::default().fg(Color::Yellow)),
+        };
+        lines.push(Line::from(Span::styled(
+            op.action.describe(),
+            Style::default().add_modifier(Modifier::BOLD),
+        )));
+        lines.push(Line::from(Span::styled(format!("  {}", status), style)));
+    }
+    let p = Paragraph::new(lines)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(format!("Operations ({})", app.operations.all().len())),
+        )
+        .wrap(ratatui::widgets::Wrap { trim: false });
+    f.render_widget(p, area);
+}
+
+fn draw_metrics(f: &mut Frame, app: &App, area: Rect) {
+    let title = match &app.metrics {
+        Some(m) => format!(
+            "Metrics: {} (last {})",
+            m.instance_id,
+            app.metrics_window.label()
+        ),
+        None => format!("Metrics (last {})", app.metrics_window.label()),
+    };
+    let block = Block::default().borders(Borders::ALL).title(title);
+
+    let Some(metrics) = &app.metrics else {
+        let text = if app.cloudwatch_client.is_none() {
+            "CloudWatch client unavailable"
+        } else {
+            "Loading metrics..."
+        };
+        f.render_widget(Paragraph::new(text).block(block), area);
+        return;
+    };
+
+    let inner = block.inner(area);
+    f.render_widget(block, area);
+
+    // (title, data scaled to u64, optional fixed maximum)
+    let mut series: Vec<(String, Vec<u64>, Option<u64>)> = vec![
+        (
+            format!(
+                "CPU {}",
+                metrics
+                    .cpu_utilization
+                    .last()
+                    .map(|v| format!("{:.1}%", v))
+                    .unwrap_or_else(|| "-".to_string())
+            ),
+            // Hundredths of a percent, against a fixed 100% ceiling
+            metrics
+                .cpu_utilization
+                .iter()
+                .map(|v| (v * 100.0) as u64)
+                .collect(),
+            Some(10_000),
+        ),
+        (
+            format!("Net In {}", last_bytes(&metrics.network_in)),
+            metrics.network_in.iter().map(|v| *v as u64).collect(),
+            None,
+        ),
+        (
+            format!("Net Out {}", last_bytes(&metrics.network_out)),
+            metrics.network_out.iter().map(|v| *v as u64).collect(),
+            None,
+        ),
+    ];
+    if let Some(credits) = &metrics.cpu_credit_balance {
+        series.push((
+            format!(
+                "Credits {}",
+                credits
+                    .last()
+                    .map(|v| format!("{:.0}", v))
+                    .unwrap_or_else(|| "-".to_string())
+            ),
+            credits.iter().map(|v| *v as u64).collect(),
+            None,
+        ));
+    }
+
+    let columns = Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(vec![
+            Constraint::Ratio(1, series.len() as u32);
+            series.len()
+        ])
+        .split(inner);
+
+    for ((title, data, max), column) in series.iter().zip(columns.iter()) {
+        let mut sparkline = Sparkline::default()
+            .block(Block::default().title(title.as_str()))
+            .data(data)
+            .style(Style::default().fg(Color::Cyan));
+        if let Some(max) = max {
+            sparkline = sparkline.max(*max);
+        }
+        f.render_widget(sparkline, *column);
+    }
+}
+
+fn last_bytes(values: &[f64]) -> String {
+    values
+        .last()
+        .map(|v| format_bytes(*v))
+        .unwrap_or_else(|| "-".to_string())
+}
+
+fn format_bytes(value: f64) -> String {
+    const KIB: f64 = 1024.0;
+    if value >= KIB * KIB * KIB {
+        format!("{:.1} GiB", value / (KIB * KIB * KIB))
+    } else if value >= KIB * KIB {
+        format!("{:.1} MiB", value / (KIB * KIB))
+    } else if value >= KIB {
+        format!("{:.1} KiB", value / KIB)
+    } else {
+        format!("{:.0} B", value)
+    }
+}
+
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
 fn draw_statusbar(f: &mut Frame, app: &App, area: Rect) {
     let status_text = match &app.state {
+        AppState::List if app.read_only => format!(
+            "Filter: '{}' | q: Quit | f: Filter | c: Prices | p: Spot | o: Right-size | I: Idle | D: Permissions | L: Audit Log | m: Metrics{} | \u{2191}\u{2193}: Select",
+            app.filter,
+            if app.show_metrics { " | w: Window" } else { "" }
+        ),
         AppState::List => format!(
-            "Filter: '{}' | q: Quit | f: Filter | c: Change Type | r: Reboot | \u{2191}\u{2193}: Select",
-            app.filter
+            "Filter: '{}' | q: Quit | f: Filter | c: Change Type | r: Reboot | h: Hibernate | x/t: Stop/Term Protect | p: Spot | o: Right-size | I: Idle | D: Permissions | L: Audit Log | m: Metrics{} | \u{2191}\u{2193}: Select",
+            app.filter,
+            if app.show_metrics { " | w: Window" } else { "" }
         ),
         AppState::FilterInput => format!("Filter: '{}' | Enter: Apply | Esc: Cancel", app.filter),
+        AppState::SelectingType { .. } if app.read_only => {
+            "Esc: Close | Type to filter".to_string()
+        }
         AppState::SelectingType { .. } => {
             "Enter: Confirm | Esc: Cancel | Type to filter".to_string()
         }
         AppState::Processing(msg) => {
-            if msg.starts_with("Error") {
+            if msg.starts_with("Error") && app.error_detail.is_some() {
+                if app.error_expanded {
+                    "Error Occurred | e: Collapse | Enter/Esc: Dismiss".to_string()
+                } else {
+                    "Error Occurred | e: Expand Details | Enter/Esc: Dismiss".to_string()
+                }
+            } else if msg.starts_with("Error") {
                 "Error Occurred | Enter/Esc: Dismiss".to_string()
-            } else {
+            } else if app.operations.is_empty() {
                 "Processing...".to_string()
+            } else {
+                "Processing... | C: Cancel | Esc: Hide".to_string()
             }
         }
         AppState::ConfirmReboot(_) => {
             "y/Enter: Confirm Reboot | f: Force | n/Esc: Cancel".to_string()
         }
+        AppState::ConfirmAction { expected: None, .. } => {
+            "y/Enter: Confirm | n/Esc: Cancel".to_string()
+        }
+        AppState::ConfirmAction { .. } => "Enter: Confirm | Esc: Cancel".to_string(),
+        AppState::AuditLog { .. } => {
+            "Type: Filter Instance | Tab: Cycle Action | \u{2191}\u{2193}: Select | Esc: Close"
+                .to_string()
+        }
+        AppState::SpotDetails { .. } | AppState::PermissionReport { .. } => {
+            "Enter/Esc: Close".to_string()
+        }
+        AppState::IdleInstances { .. } if app.read_only => {
+            "\u{2191}\u{2193}: Select | Esc: Close".to_string()
+        }
+        AppState::IdleInstances { .. } => {
+            "Space: Mark | a: Mark All | s: Stop Marked | \u{2191}\u{2193}: Select | Esc: Close"
+                .to_string()
+        }
+        AppState::Recommendations { .. } if app.read_only => {
+            "\u{2191}\u{2193}: Select | Esc: Close".to_string()
+        }
+        AppState::Recommendations { .. } => {
+            "Enter: Resize to Suggestion | \u{2191}\u{2193}: Select | Esc: Cancel".to_string()
+        }
+    };
+    let status_text = if matches!(app.state, AppState::List) && !app.operations.is_empty() {
+        format!("{} | C: Cancel Op", status_text)
+    } else {
+        status_text
+    };
+    let badge_width = if app.read_only { 12 } else { 0 };
+    let frame = SPINNER[app.spinner % SPINNER.len()];
+    let loading = app
+        .loading
+        .iter()
+        .map(|l| format!("{} {}", frame, l.label()))
+        .collect::<Vec<_>>()
+        .join("  ");
+    let loading_width = if loading.is_empty() {
+        0
+    } else {
+        loading.chars().count() as u16 + 2
     };
     let layout = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Min(0), Constraint::Length(25)])
+        .constraints([
+            Constraint::Length(badge_width),
+            Constraint::Min(0),
+            Constraint::Length(loading_width),
+            Constraint::Length(25),
+        ])
         .split(area);
 
+    if app.read_only {
+        let p_badge = Paragraph::new(" READ-ONLY ").style(
+            Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(Modifier::BOLD),
+        );
+        f.render_widget(p_badge, layout[0]);
+    }
+
     let p_status = Paragraph::new(status_text).style(Style::default().fg(Color::Gray));
-    f.render_widget(p_status, layout[0]);
+    f.render_widget(p_status, layout[1]);
+
+    if !loading.is_empty() {
+        let p_loading = Paragraph::new(loading)
+            .alignment(Alignment::Right)
+            .style(Style::default().fg(Color::Cyan));
+        f.render_widget(p_loading, layout[2]);
+    }
 
     if let Some(time) = &app.last_refreshed {
         let p_time = Paragraph::new(format!("Last Refreshed: {}", time))
             .alignment(Alignment::Right)
             .style(Style::default().fg(Color::Gray));
-        f.render_widget(p_time, layout[1]);
+        f.render_widget(p_time, layout[3]);
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn draw_selecting_type(
     f: &mut Frame,
+    title: &str,
     prices: &HashMap<String, Prices>,
     specs: &HashMap<String, InstanceTypeInfo>,
+    advisor: Option<&SpotAdvisor>,
+    region: &str,
     input: &str,
     options: &[String],
     selected_index: Option<usize>,
@@ -226,11 +681,7 @@ fn draw_selecting_type(
     };
 
     let input_widget = Paragraph::new(input)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .title("Select Instance Type"),
-        )
+        .block(Block::default().borders(Borders::ALL).title(title))
         .style(style);
     f.render_widget(input_widget, chunks[0]);
 
@@ -241,6 +692,8 @@ fn draw_selecting_type(
     let mut w_od = 8; // "OD Price"
     let mut w_spot = 10; // "Spot Price"
     let mut w_discount = 8; // "Discount"
+    let mut w_interrupt = 11; // "Interrupt %"
+    let mut w_savings = 7; // "Savings"
 
     let data: Vec<_> = options
         .iter()
@@ -273,20 +726,40 @@ fn draw_selecting_type(
                 _ => "-".to_string(),
             };
 
+            let advice = advisor.and_then(|a| a.get(region, s));
+            let interrupt_str = advice
+                .map(|a| a.interruption.clone())
+                .unwrap_or_else(|| "-".to_string());
+            let savings_str = advice
+                .map(|a| format!("{}%", a.savings_percent))
+                .unwrap_or_else(|| "-".to_string());
+
             w_type = w_type.max(s.len());
             w_cpu = w_cpu.max(vcpu_str.len());
             w_mem = w_mem.max(mem_str.len());
             w_od = w_od.max(od_str.len());
             w_spot = w_spot.max(spot_str.len());
             w_discount = w_discount.max(discount_str.len());
+            w_interrupt = w_interrupt.max(interrupt_str.len());
+            w_savings = w_savings.max(savings_str.len());
 
-            (i, s, vcpu_str, mem_str, od_str, spot_str, discount_str)
+            (
+                i,
+                s,
+                vcpu_str,
+                mem_str,
+                od_str,
+                spot_str,
+                discount_str,
+                interrupt_str,
+                savings_str,
+            )
         })
         .collect();
 
     let rows: Vec<Row> = data
         .into_iter()
-        .map(|(_, s, v, m, o, sp, r)| Row::new(vec![s.clone(), v, m, o, sp, r]))
+        .map(|(_, s, v, m, o, sp, r, int, sav)| Row::new(vec![s.clone(), v, m, o, sp, r, int, sav]))
         .collect();
 
     let constraints = [
@@ -296,6 +769,8 @@ fn draw_selecting_type(
         Constraint::Length(w_od as u16 + 2),
         Constraint::Length(w_spot as u16 + 2),
         Constraint::Length(w_discount as u16 + 2),
+        Constraint::Length(w_interrupt as u16 + 2),
+        Constraint::Length(w_savings as u16 + 2),
     ];
 
     let table = Table::new(rows, constraints)
@@ -307,6 +782,8 @@ fn draw_selecting_type(
                 "OD Price",
                 "Spot Price",
                 "Discount",
+                "Interrupt %",
+                "Savings",
             ])
             .style(
                 Style::default()
@@ -328,7 +805,17 @@ fn draw_selecting_type(
     f.render_stateful_widget(table, chunks[1], &mut state);
 }
 
-fn draw_processing(f: &mut Frame, msg: &str) {
+fn draw_processing(f: &mut Frame, msg: &str, detail: Option<&str>) {
+    if let Some(detail) = detail {
+        let area = centered_rect(80, 60, f.area());
+        let p = Paragraph::new(format!("{}\n\n{}", msg, detail))
+            .block(Block::default().borders(Borders::ALL).title("Error Detail"))
+            .style(Style::default().fg(Color::Red))
+            .wrap(ratatui::widgets::Wrap { trim: false });
+        f.render_widget(p, area);
+        return;
+    }
+
     let area = centered_rect(60, 20, f.area());
     let p = Paragraph::new(msg)
         .block(Block::default().borders(Borders::ALL).title("Processing"))
@@ -370,3 +857,762 @@ fn draw_confirm_reboot(f: &mut Frame, instance_id: &str) {
         .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
     f.render_widget(p, area);
 }
+
+fn draw_confirm_action(f: &mut Frame, action: &PendingAction, expected: Option<&str>, input: &str) {
+    let area = centered_rect(50, 25, f.area());
+    let block = Block::default()
+        .title("Confirm (policy)")
+        .borders(Borders::ALL);
+    let text = match expected {
+        None => format!("{}? (y/n)", action.describe()),
+        Some(expected) => format!(
+            "{}?\n\nType '{}' to confirm:\n> {}",
+            action.describe(),
+            expected,
+            input
+        ),
+    };
+    let p = Paragraph::new(text)
+        .block(block)
+        .alignment(Alignment::Center)
+        .style(Style::default().fg(Color::Red).add_modifier(Modifier::BOLD));
+    f.render_widget(p, area);
+}
+
+fn draw_audit_log(
+    f: &mut Frame,
+    records: Option<&[AuditRecord]>,
+    instance_filter: &str,
+    action_filter: Option<&str>,
+    selected_index: Option<usize>,
+) {
+    let area = centered_rect(90, 70, f.area());
+    let title = format!(
+        "Audit Log | Instance: '{}' | Action: {}",
+        instance_filter,
+        action_filter.unwrap_or("all")
+    );
+
+    let Some(records) = records else {
+        let p = Paragraph::new("Loading audit log...")
+            .block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(p, area);
+        return;
+    };
+
+    let rows: Vec<Row> = records
+        .iter()
+        .filter(|r| r.matches(instance_filter, action_filter))
+        .map(|r| {
+            let parameters = r
+                .parameters
+                .iter()
+                .map(|(k, v)| format!("{}={}", k, v))
+                .collect::<Vec<_>>()
+                .join(" ");
+            // Keep the date and time, drop fractional seconds and offset
+            let timestamp = r
+                .timestamp
+                .get(..19)
+                .unwrap_or(&r.timestamp)
+                .replace('T', " ");
+            Row::new(vec![
+                Cell::from(timestamp),
+                Cell::from(r.instance_id.clone()),
+                Cell::from(r.action.clone()).style(Style::default().fg(Color::Cyan)),
+                Cell::from(parameters),
+                Cell::from(r.outcome.clone()).style(if r.outcome == "ok" {
+                    Style::default().fg(Color::Green)
+                } else {
+                    Style::default().fg(Color::Red)
+                }),
+                Cell::from(r.region.clone()),
+                Cell::from(r.identity.clone()),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(21),
+            Constraint::Length(22),
+            Constraint::Length(24),
+            Constraint::Min(10),
+            Constraint::Length(15),
+            Constraint::Min(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Time (UTC)",
+            "Instance ID",
+            "Action",
+            "Parameters",
+            "Outcome",
+            "Region",
+            "Identity",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .bottom_margin(1),
+    )
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = TableState::default();
+    state.select(selected_index);
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+fn draw_permission_report(f: &mut Frame, instance_id: &str, checks: Option<&[PermissionCheck]>) {
+    let area = centered_rect(70, 40, f.area());
+    let block = Block::default()
+        .title(format!("Permissions (dry run): {}", instance_id))
+        .borders(Borders::ALL);
+
+    let Some(checks) = checks else {
+        let p = Paragraph::new("Sending dry-run requests...").block(block);
+        f.render_widget(p, area);
+        return;
+    };
+
+    let rows: Vec<Row> = checks
+        .iter()
+        .map(|c| {
+            let (result, detail, color) = match &c.permission {
+                Permission::Allowed => ("allowed", String::new(), Color::Green),
+                Permission::Denied => ("DENIED", String::new(), Color::Red),
+                Permission::Unknown(msg) => ("unknown", msg.clone(), Color::Yellow),
+            };
+            Row::new(vec![
+                Cell::from(c.operation),
+                Cell::from(result).style(Style::default().fg(color)),
+                Cell::from(detail),
+            ])
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(35),
+            Constraint::Length(9),
+            Constraint::Min(0),
+        ],
+    )
+    .header(
+        Row::new(vec!["Operation", "Result", "Detail"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .block(block);
+    f.render_widget(table, area);
+}
+
+fn draw_spot_details(f: &mut Frame, instance_id: &str, request: Option<&SpotRequestInfo>) {
+    let area = centered_rect(60, 40, f.area());
+    let block = Block::default()
+        .title(format!("Spot Details: {}", instance_id))
+        .borders(Borders::ALL);
+
+    let text = match request {
+        None => "Loading spot request...".to_string(),
+        Some(r) => {
+            let field = |v: &Option<String>| v.clone().unwrap_or_else(|| "-".to_string());
+            let mut lines = vec![
+                format!("Request ID:             {}", r.id),
+                format!("Request Type:           {}", field(&r.request_type)),
+                format!("Request State:          {}", field(&r.state)),
+                format!("Status:                 {}", field(&r.status_code)),
+                format!("Max Price:              {}", field(&r.max_price)),
+                format!(
+                    "Interruption Behavior:  {}",
+                    field(&r.interruption_behavior)
+                ),
+            ];
+            if let Some(msg) = &r.status_message {
+                lines.push(String::new());
+                lines.push(msg.clone());
+            }
+            lines.push(String::new());
+            if r.is_persistent() {
+                lines.push(
+                    "Resize stops the instance; the persistent request relaunches it on spot capacity."
+                        .to_string(),
+                );
+            } else {
+                lines.push(
+                    "One-time request: the instance cannot be stopped, so resizing is unavailable."
+                        .to_string(),
+                );
+            }
+            lines.join("\n")
+        }
+    };
+
+    let p = Paragraph::new(text)
+        .block(block)
+        .wrap(ratatui::widgets::Wrap { trim: false });
+    f.render_widget(p, area);
+}
+
+fn draw_recommendations(
+    f: &mut Frame,
+    instance_id: &str,
+    stats: Option<&UtilizationStats>,
+    recommendations: &[Recommendation],
+    selected_index: Option<usize>,
+) {
+    let area = centered_rect(80, 60, f.area());
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(0)])
+        .split(area);
+
+    let summary = match stats {
+        None => "Loading utilization history...".to_string(),
+        Some(s) => format!(
+            "Last {} days: CPU avg {:.1}%, p95 peak {:.1}%, memory peak {}",
+            s.days,
+            s.cpu_avg,
+            s.cpu_peak,
+            s.mem_peak
+                .map(|m| format!("{:.1}%", m))
+                .unwrap_or_else(|| "n/a (no CloudWatch agent)".to_string())
+        ),
+    };
+    let summary_widget = Paragraph::new(summary).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .title(format!("Right-size {}", instance_id)),
+    );
+    f.render_widget(summary_widget, chunks[0]);
+
+    let rows: Vec<Row> = recommendations
+        .iter()
+        .map(|r| {
+            let savings_style = if r.monthly_savings >= 0.0 {
+                Style::default().fg(Color::Green)
+            } else {
+                Style::default().fg(Color::Red)
+            };
+            Row::new(vec![
+                Cell::from(r.instance_type.clone()),
+                Cell::from(r.kind.label()),
+                Cell::from(
+                    r.vcpu
+                        .map(|v| format!("{:>4}", v))
+                        .unwrap_or_else(|| "   -".to_string()),
+                ),
+                Cell::from(
+                    r.memory_mib
+                        .map(|m| format!("{:>8.1}", m as f64 / 1024.0))
+                        .unwrap_or_else(|| "       -".to_string()),
+                ),
+                Cell::from(format!("${:.4}", r.hourly_price)),
+                Cell::from(format!("${:.2}", r.monthly_savings)).style(savings_style),
+            ])
+        })
+        .collect();
+
+    let empty_title = if stats.is_some() && recommendations.is_empty() {
+        "Suggestions (none: current type fits, or prices still loading)"
+    } else {
+        "Suggestions"
+    };
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(16),
+            Constraint::Length(16),
+            Constraint::Length(6),
+            Constraint::Length(11),
+            Constraint::Length(10),
+            Constraint::Length(14),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "Type",
+            "Suggestion",
+            "CPUs",
+            "Mem (GiB)",
+            "OD Price",
+            "Savings/mo",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .bottom_margin(1),
+    )
+    .block(Block::default().borders(Borders::ALL).title(empty_title))
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = TableState::default();
+    state.select(selected_index);
+    f.render_stateful_widget(table, chunks[1], &mut state);
+}
+
+fn draw_idle_instances(
+    f: &mut Frame,
+    prices: &HashMap<String, Prices>,
+    candidates: &[IdleCandidate],
+    selected_index: Option<usize>,
+    scanning: bool,
+) {
+    let area = centered_rect(80, 60, f.area());
+
+    let mut total_wasted = 0.0;
+    let rows: Vec<Row> = candidates
+        .iter()
+        .map(|c| {
+            let wasted = prices
+                .get(&c.instance_type)
+                .and_then(|p| p.on_demand)
+                .map(|p| p * c.hours as f64);
+            total_wasted += wasted.unwrap_or(0.0);
+            Row::new(vec![
+                Cell::from(if c.marked { "[x]" } else { "[ ]" }),
+                Cell::from(c.instance_id.clone()),
+                Cell::from(c.name.clone().unwrap_or_else(|| "-".to_string())),
+                Cell::from(c.instance_type.clone()).style(Style::default().fg(Color::Cyan)),
+                Cell::from(format!("{:.1}%", c.max_cpu)),
+                Cell::from(format_bytes(c.max_network_bytes)),
+                Cell::from(
+                    wasted
+                        .map(|w| format!("${:.2}", w))
+                        .unwrap_or_else(|| "-".to_string()),
+                )
+                .style(Style::default().fg(Color::Red)),
+            ])
+        })
+        .collect();
+
+    let title = if scanning {
+        "Idle Instances (scanning CloudWatch...)".to_string()
+    } else if candidates.is_empty() {
+        "Idle Instances (none found)".to_string()
+    } else {
+        format!(
+            "Idle Instances: {} found, ${:.2} spent while idle",
+            candidates.len(),
+            total_wasted
+        )
+    };
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(4),
+            Constraint::Length(21),
+            Constraint::Min(10),
+            Constraint::Length(14),
+            Constraint::Length(9),
+            Constraint::Length(11),
+            Constraint::Length(10),
+        ],
+    )
+    .header(
+        Row::new(vec![
+            "",
+            "Instance ID",
+            "Name",
+            "Type",
+            "Max CPU",
+            "Max Net/h",
+            "Wasted",
+        ])
+        .style(
+            Style::default()
+                .fg(Color::Yellow)
+                .add_modifier(Modifier::BOLD),
+        )
+        .bottom_margin(1),
+    )
+    .block(Block::default().borders(Borders::ALL).title(title))
+    .row_highlight_style(
+        Style::default()
+            .bg(Color::Blue)
+            .fg(Color::White)
+            .add_modifier(Modifier::BOLD),
+    );
+
+    let mut state = TableState::default();
+    state.select(selected_index);
+    f.render_stateful_widget(table, area, &mut state);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ec2::{Ec2Client, InstanceInfo, Protection, StatusChecks};
+    use crate::fake;
+    use crate::rightsizing::RecommendationKind;
+    use ratatui::{Terminal, backend::TestBackend};
+    use std::collections::BTreeMap;
+
+    const SIZES: [(u16, u16); 3] = [(80, 24), (120, 40), (200, 50)];
+
+    fn named(id: &str, name: &str, instance_type: &str, state: &str) -> InstanceInfo {
+        let mut info = fake::instance(id, instance_type, state);
+        info.name = Some(name.to_string());
+        info
+    }
+
+    /// One instance per list state; launch times are left unset so runtimes stay stable.
+    fn instances() -> Vec<InstanceInfo> {
+        let mut web = named("i-0000000000000001", "web", "t3.medium", "running");
+        web.public_ip = Some("203.0.113.10".to_string());
+        let mut dev = named("i-0000000000000003", "dev", "t3.large", "stopped");
+        dev.hibernation_configured = true;
+        dev.state_reason = Some("Client.UserInitiatedHibernate".to_string());
+        let mut worker = named("i-0000000000000006", "worker", "c6g.large", "running");
+        worker.architecture = Some("arm64".to_string());
+        worker.availability_zone = Some("us-east-1b".to_string());
+        worker.instance_lifecycle = Some("spot".to_string());
+        worker.spot_instance_request_id = Some("sir-00000001".to_string());
+        vec![
+            web,
+            named("i-0000000000000002", "db", "r5.large", "stopped"),
+            dev,
+            named("i-0000000000000004", "ci", "m5.xlarge", "pending"),
+            named("i-0000000000000005", "build", "t3.small", "stopping"),
+            worker,
+        ]
+    }
+
+    fn test_app() -> App {
+        let mut app = App::for_test(Ec2Client::new_mock(), instances());
+        app.list_state.select(Some(0));
+        app.protection.insert(
+            "i-0000000000000001".to_string(),
+            Some(Protection {
+                stop: false,
+                termination: true,
+            }),
+        );
+        app.protection.insert(
+            "i-0000000000000002".to_string(),
+            Some(Protection {
+                stop: true,
+                termination: true,
+            }),
+        );
+        app.protection.insert(
+            "i-0000000000000003".to_string(),
+            Some(Protection::default()),
+        );
+        for (id, instance) in [
+            ("i-0000000000000001", "ok"),
+            ("i-0000000000000006", "impaired"),
+        ] {
+            app.status_checks.insert(
+                id.to_string(),
+                StatusChecks {
+                    system: "ok".to_string(),
+                    instance: instance.to_string(),
+                    ebs: "ok".to_string(),
+                },
+            );
+        }
+        app
+    }
+
+    fn with_prices(app: &mut App) {
+        for (name, vcpu, memory_mib, on_demand) in [
+            ("t3.small", 2, 2048, 0.0208),
+            ("t3.medium", 2, 4096, 0.0416),
+            ("t3.large", 2, 8192, 0.0832),
+        ] {
+            app.instance_type_map.insert(
+                name.to_string(),
+                InstanceTypeInfo {
+                    name: name.to_string(),
+                    architectures: vec!["x86_64".to_string()],
+                    vcpu: Some(vcpu),
+                    memory_mib: Some(memory_mib),
+                },
+            );
+            app.type_prices.insert(
+                name.to_string(),
+                Prices {
+                    on_demand: Some(on_demand),
+                    spot: Some(on_demand * 0.35),
+                },
+            );
+        }
+    }
+
+    fn selector() -> AppState {
+        AppState::SelectingType {
+            input: "t3".to_string(),
+            options: vec![
+                "t3.small".to_string(),
+                "t3.medium".to_string(),
+                "t3.large".to_string(),
+            ],
+            selected_index: Some(1),
+            default_mode_active: false,
+        }
+    }
+
+    /// Renders `app` at every size in `SIZES` and compares each frame with its snapshot.
+    fn assert_screens(name: &str, app: &mut App) {
+        for (width, height) in SIZES {
+            let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+            terminal.draw(|f| draw(f, app)).unwrap();
+            insta::assert_snapshot!(format!("{}_{}x{}", name, width, height), terminal.backend());
+        }
+    }
+
+    #[test]
+    fn test_list() {
+        assert_screens("list", &mut test_app());
+    }
+
+    #[test]
+    fn test_list_empty() {
+        let mut app = App::for_test(Ec2Client::new_mock(), vec![]);
+        assert_screens("list_empty", &mut app);
+    }
+
+    #[test]
+    fn test_list_loading() {
+        let mut app = App::for_test(Ec2Client::new_mock(), vec![]);
+        app.loading = vec![Load::Instances, Load::InstanceTypes, Load::Prices];
+        app.spinner = 3;
+        assert_screens("list_loading", &mut app);
+    }
+
+    #[test]
+    fn test_operations_panel() {
+        let mut app = test_app();
+        app.operations.submit(PendingAction::Resize {
+            instance_id: "i-0000000000000001".to_string(),
+            new_type: "t3.large".to_string(),
+        });
+        app.operations
+            .set_step("i-0000000000000001", "Waiting for stop...");
+        app.operations
+            .submit(PendingAction::Start("i-0000000000000001".to_string()));
+        app.operations
+            .submit(PendingAction::Stop("i-0000000000000002".to_string()));
+        assert_screens("operations_panel", &mut app);
+    }
+
+    #[test]
+    fn test_list_read_only_with_banner() {
+        let mut app = test_app();
+        app.read_only = true;
+        app.credentials_problem = Some(ErrorKind::ExpiredCredentials);
+        app.last_refreshed = Some("12:34:56".to_string());
+        assert_screens("list_read_only_banner", &mut app);
+    }
+
+    #[test]
+    fn test_filter_input() {
+        let mut app = test_app();
+        app.filter = "web".to_string();
+        app.filtered_instances.truncate(1);
+        app.state = AppState::FilterInput;
+        assert_screens("filter_input", &mut app);
+    }
+
+    #[test]
+    fn test_selector_without_prices() {
+        let mut app = test_app();
+        app.state = selector();
+        assert_screens("selector_without_prices", &mut app);
+    }
+
+    #[test]
+    fn test_selector_with_prices() {
+        let mut app = test_app();
+        with_prices(&mut app);
+        app.state = selector();
+        assert_screens("selector_with_prices", &mut app);
+    }
+
+    #[test]
+    fn test_selector_for_spot_instance() {
+        let mut app = test_app();
+        with_prices(&mut app);
+        app.list_state.select(Some(5));
+        app.state = selector();
+        assert_screens("selector_spot", &mut app);
+    }
+
+    #[test]
+    fn test_confirm_reboot() {
+        let mut app = test_app();
+        app.state = AppState::ConfirmReboot("i-0000000000000001".to_string());
+        assert_screens("confirm_reboot", &mut app);
+    }
+
+    #[test]
+    fn test_confirm_action_typed() {
+        let mut app = test_app();
+        app.state = AppState::ConfirmAction {
+            action: PendingAction::Stop("i-0000000000000001".to_string()),
+            expected: Some("web".to_string()),
+            input: "we".to_string(),
+        };
+        assert_screens("confirm_action_typed", &mut app);
+    }
+
+    #[test]
+    fn test_processing() {
+        let mut app = test_app();
+        app.state = AppState::Processing("Stopping i-0000000000000001...".to_string());
+        assert_screens("processing", &mut app);
+    }
+
+    #[test]
+    fn test_error_collapsed_and_expanded() {
+        let mut app = test_app();
+        app.state =
+            AppState::Processing("Error: Not authorized to stop i-0000000000000001".to_string());
+        app.error_detail = Some(
+            "UnauthorizedOperation: You are not authorized to perform this operation.".to_string(),
+        );
+        assert_screens("error_collapsed", &mut app);
+        app.error_expanded = true;
+        assert_screens("error_expanded", &mut app);
+    }
+
+    #[test]
+    fn test_spot_details() {
+        let mut app = test_app();
+        app.state = AppState::SpotDetails {
+            instance_id: "i-0000000000000006".to_string(),
+            request: None,
+        };
+        assert_screens("spot_details_loading", &mut app);
+        app.state = AppState::SpotDetails {
+            instance_id: "i-0000000000000006".to_string(),
+            request: Some(SpotRequestInfo {
+                id: "sir-00000001".to_string(),
+                request_type: Some("persistent".to_string()),
+                state: Some("active".to_string()),
+                status_code: Some("fulfilled".to_string()),
+                status_message: Some("Your spot request is fulfilled.".to_string()),
+                max_price: Some("0.0500".to_string()),
+                interruption_behavior: Some("stop".to_string()),
+            }),
+        };
+        assert_screens("spot_details", &mut app);
+    }
+
+    #[test]
+    fn test_recommendations() {
+        let mut app = test_app();
+        app.state = AppState::Recommendations {
+            instance_id: "i-0000000000000001".to_string(),
+            stats: Some(UtilizationStats {
+                days: 14,
+                cpu_avg: 4.2,
+                cpu_peak: 11.5,
+                mem_peak: None,
+            }),
+            recommendations: vec![
+                Recommendation {
+                    instance_type: "t3.small".to_string(),
+                    kind: RecommendationKind::Downsize,
+                    vcpu: Some(2),
+                    memory_mib: Some(2048),
+                    hourly_price: 0.0208,
+                    monthly_savings: 15.18,
+                },
+                Recommendation {
+                    instance_type: "t4g.medium".to_string(),
+                    kind: RecommendationKind::CheaperFamily,
+                    vcpu: Some(2),
+                    memory_mib: Some(4096),
+                    hourly_price: 0.0336,
+                    monthly_savings: 5.84,
+                },
+            ],
+            selected_index: Some(0),
+        };
+        assert_screens("recommendations", &mut app);
+    }
+
+    #[test]
+    fn test_idle_instances() {
+        let mut app = test_app();
+        app.state = AppState::IdleInstances {
+            candidates: vec![IdleCandidate {
+                instance_id: "i-0000000000000001".to_string(),
+                name: Some("web".to_string()),
+                instance_type: "t3.medium".to_string(),
+                max_cpu: 1.3,
+                max_network_bytes: 20480.0,
+                hours: 24,
+                marked: true,
+            }],
+            selected_index: Some(0),
+            scanning: false,
+        };
+        assert_screens("idle_instances", &mut app);
+    }
+
+    #[test]
+    fn test_audit_log() {
+        let mut app = test_app();
+        app.state = AppState::AuditLog {
+            records: Some(vec![AuditRecord {
+                timestamp: "2026-10-18T09:15:00Z".to_string(),
+                identity: "arn:aws:iam::123456789012:user/ops".to_string(),
+                region: "us-east-1".to_string(),
+                instance_id: "i-0000000000000001".to_string(),
+                action: "modify_instance_type".to_string(),
+                parameters: BTreeMap::from([("type".to_string(), "t3.large".to_string())]),
+                outcome: "ok".to_string(),
+            }]),
+            instance_filter: String::new(),
+            action_filter: None,
+            selected_index: Some(0),
+        };
+        assert_screens("audit_log", &mut app);
+    }
+
+    #[test]
+    fn test_permission_report() {
+        let mut app = test_app();
+        app.state = AppState::PermissionReport {
+            instance_id: "i-0000000000000001".to_string(),
+            checks: Some(vec![
+                PermissionCheck {
+                    operation: "StopInstances",
+                    permission: Permission::Allowed,
+                },
+                PermissionCheck {
+                    operation: "ModifyInstanceAttribute",
+                    permission: Permission::Denied,
+                },
+                PermissionCheck {
+                    operation: "StartInstances",
+                    permission: Permission::Unknown("IncorrectInstanceState".to_string()),
+                },
+            ]),
+        };
+        assert_screens("permission_report", &mut app);
+    }
+}